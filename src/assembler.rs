@@ -0,0 +1,476 @@
+//! Assembles Cowgod-style source, as produced by the
+//! [disassembler](crate::disassembler), into ROM images.
+//!
+//! ```text
+//! SPEED EQU 2             ; constants
+//! include "sprites.asm"   ; paths are relative to the including file
+//!
+//! loop:
+//!     LD I, ship
+//!     DRW V0, V1, 4
+//!     ADD V0, SPEED
+//!     JP loop
+//!
+//! ship:
+//!     db 0x18, 0x3C, 0x7E, 0xFF
+//!     dw 0x1234
+//! ```
+//!
+//! Mnemonics, registers and directives are case-insensitive; labels and
+//! constants are not. Numbers are decimal, `0x` hex or `0b` binary, and
+//! operands may add or subtract symbols and numbers, e.g. `ship+2`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::machine::ROM_START_ADDRESS;
+
+/// How deeply `include`s may nest before assembly gives up.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// An error in the source, with the file (if any) and 1-based line number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub file: Option<PathBuf>,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}: {}", file.display(), self.line, self.message),
+            None => write!(f, "line {}: {}", self.line, self.message),
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Assembles source text into a ROM image loaded at [`ROM_START_ADDRESS`].
+/// Includes are resolved relative to the current directory.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let mut lines = Vec::new();
+    expand(source, None, Path::new(""), 0, &mut lines)?;
+    Assembler::default().run(&lines)
+}
+
+/// Assembles the source file at `path`.
+pub fn assemble_file(path: &Path) -> Result<Vec<u8>, AsmError> {
+    let source = fs::read_to_string(path).map_err(|err| AsmError {
+        file: Some(path.to_path_buf()),
+        line: 0,
+        message: err.to_string(),
+    })?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut lines = Vec::new();
+    expand(&source, Some(path), directory, 0, &mut lines)?;
+    Assembler::default().run(&lines)
+}
+
+/// A source line with comments removed, tagged with where it came from.
+struct Line {
+    file: Option<PathBuf>,
+    number: usize,
+    text: String,
+}
+
+impl Line {
+    fn error(&self, message: impl Into<String>) -> AsmError {
+        AsmError {
+            file: self.file.clone(),
+            line: self.number,
+            message: message.into(),
+        }
+    }
+}
+
+/// Splits `source` into lines, replacing `include` directives with the
+/// lines of the included file.
+fn expand(
+    source: &str,
+    file: Option<&Path>,
+    directory: &Path,
+    depth: usize,
+    lines: &mut Vec<Line>,
+) -> Result<(), AsmError> {
+    for (index, text) in source.lines().enumerate() {
+        let line = Line {
+            file: file.map(Path::to_path_buf),
+            number: index + 1,
+            text: strip_comment(text).trim().to_string(),
+        };
+
+        let Some(argument) = directive(&line.text, "include") else {
+            lines.push(line);
+            continue;
+        };
+
+        if depth >= MAX_INCLUDE_DEPTH {
+            return Err(line.error("includes nested too deeply"));
+        }
+        let name = argument
+            .strip_prefix('"')
+            .and_then(|name| name.strip_suffix('"'))
+            .ok_or_else(|| line.error("expected a quoted file name after `include`"))?;
+        let path = directory.join(name);
+        let included = fs::read_to_string(&path)
+            .map_err(|err| line.error(format!("cannot include {}: {}", path.display(), err)))?;
+        let included_directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+        expand(
+            &included,
+            Some(&path),
+            &included_directory,
+            depth + 1,
+            lines,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn strip_comment(text: &str) -> &str {
+    let mut quoted = false;
+    for (index, character) in text.char_indices() {
+        match character {
+            '"' => quoted = !quoted,
+            ';' if !quoted => return &text[..index],
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Returns the rest of `text` if it starts with the case-insensitive
+/// keyword `name` followed by whitespace.
+fn directive<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let (keyword, rest) = text.split_once(char::is_whitespace)?;
+    keyword.eq_ignore_ascii_case(name).then(|| rest.trim())
+}
+
+#[derive(Default)]
+struct Assembler {
+    symbols: HashMap<String, i64>,
+}
+
+impl Assembler {
+    fn run(mut self, lines: &[Line]) -> Result<Vec<u8>, AsmError> {
+        // First pass: find the address of every label and the value of every
+        // constant, so the second pass can resolve forward references.
+        let mut address = ROM_START_ADDRESS as i64;
+        for line in lines {
+            let statement = self.define_label(line, address)?;
+            if statement.is_empty() {
+                continue;
+            }
+            if let Some((name, expression)) = constant(statement) {
+                let value = self.evaluate(line, expression)?;
+                self.define(line, name, value)?;
+                continue;
+            }
+            address += size(statement) as i64;
+            if address > 0x10000 {
+                return Err(line.error("program does not fit in 64 KB"));
+            }
+        }
+
+        // Second pass: encode everything with all symbols known.
+        let mut output = Vec::new();
+        for line in lines {
+            let statement = label(&line.text).map_or(line.text.as_str(), |(_, rest)| rest);
+            if statement.is_empty() || constant(statement).is_some() {
+                continue;
+            }
+            self.encode(line, statement, &mut output)?;
+        }
+
+        Ok(output)
+    }
+
+    /// Records a leading `name:` label and returns the rest of the line.
+    fn define_label<'a>(&mut self, line: &'a Line, address: i64) -> Result<&'a str, AsmError> {
+        match label(&line.text) {
+            Some((name, rest)) => {
+                self.define(line, name, address)?;
+                Ok(rest)
+            }
+            None => Ok(&line.text),
+        }
+    }
+
+    fn define(&mut self, line: &Line, name: &str, value: i64) -> Result<(), AsmError> {
+        if !is_identifier(name) {
+            return Err(line.error(format!("invalid symbol name `{}`", name)));
+        }
+        if self.symbols.insert(name.to_string(), value).is_some() {
+            return Err(line.error(format!("`{}` is defined more than once", name)));
+        }
+        Ok(())
+    }
+
+    fn encode(&self, line: &Line, statement: &str, output: &mut Vec<u8>) -> Result<(), AsmError> {
+        let (mnemonic, operands) = split_statement(statement);
+        let mnemonic = mnemonic.to_ascii_uppercase();
+
+        match mnemonic.as_str() {
+            "DB" => {
+                for operand in &operands {
+                    output.push(self.value(line, operand, 0xFF)? as u8);
+                }
+                return Ok(());
+            }
+            "DW" => {
+                for operand in &operands {
+                    let word = self.value(line, operand, 0xFFFF)?;
+                    output.extend_from_slice(&word.to_be_bytes());
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let operands: Vec<Operand> = operands
+            .iter()
+            .map(|operand| Operand::parse(operand))
+            .collect();
+
+        let opcode = self.opcode(line, &mnemonic, &operands)?;
+        output.extend_from_slice(&opcode.to_be_bytes());
+
+        if let [Operand::I, Operand::Long(expression)] = operands.as_slice() {
+            let address = self.value(line, expression, 0xFFFF)?;
+            output.extend_from_slice(&address.to_be_bytes());
+        }
+
+        Ok(())
+    }
+
+    fn opcode(&self, line: &Line, mnemonic: &str, operands: &[Operand]) -> Result<u16, AsmError> {
+        use Operand::*;
+
+        let addr = |expression: &str| self.value(line, expression, 0xFFF);
+        let byte = |expression: &str| self.value(line, expression, 0xFF);
+        let nibble = |expression: &str| self.value(line, expression, 0xF);
+        let xy = |x: u8, y: u8| (x as u16) << 8 | (y as u16) << 4;
+        let fx = |x: u8, low: u16| 0xF000 | (x as u16) << 8 | low;
+
+        let opcode = match (mnemonic, operands) {
+            ("CLS", []) => 0x00E0,
+            ("RET", []) => 0x00EE,
+            ("SCD", [Value(n)]) => 0x00C0 | nibble(n)?,
+            ("SCU", [Value(n)]) => 0x00D0 | nibble(n)?,
+            ("SCR", []) => 0x00FB,
+            ("SCL", []) => 0x00FC,
+            ("EXIT", []) => 0x00FD,
+            ("LOW", []) => 0x00FE,
+            ("HIGH", []) => 0x00FF,
+            ("JP", [Value(target)]) => 0x1000 | addr(target)?,
+            ("JP", [V(0), Value(target)]) => 0xB000 | addr(target)?,
+            ("CALL", [Value(target)]) => 0x2000 | addr(target)?,
+            ("SE", [V(x), Value(nn)]) => 0x3000 | (*x as u16) << 8 | byte(nn)?,
+            ("SNE", [V(x), Value(nn)]) => 0x4000 | (*x as u16) << 8 | byte(nn)?,
+            ("SE", [V(x), V(y)]) => 0x5000 | xy(*x, *y),
+            ("SAVE", [V(x), V(y)]) => 0x5002 | xy(*x, *y),
+            ("LOAD", [V(x), V(y)]) => 0x5003 | xy(*x, *y),
+            ("LD", [V(x), Value(nn)]) => 0x6000 | (*x as u16) << 8 | byte(nn)?,
+            ("ADD", [V(x), Value(nn)]) => 0x7000 | (*x as u16) << 8 | byte(nn)?,
+            ("LD", [V(x), V(y)]) => 0x8000 | xy(*x, *y),
+            ("OR", [V(x), V(y)]) => 0x8001 | xy(*x, *y),
+            ("AND", [V(x), V(y)]) => 0x8002 | xy(*x, *y),
+            ("XOR", [V(x), V(y)]) => 0x8003 | xy(*x, *y),
+            ("ADD", [V(x), V(y)]) => 0x8004 | xy(*x, *y),
+            ("SUB", [V(x), V(y)]) => 0x8005 | xy(*x, *y),
+            ("SHR", [V(x), V(y)]) => 0x8006 | xy(*x, *y),
+            ("SHR", [V(x)]) => 0x8006 | xy(*x, *x),
+            ("SUBN", [V(x), V(y)]) => 0x8007 | xy(*x, *y),
+            ("SHL", [V(x), V(y)]) => 0x800E | xy(*x, *y),
+            ("SHL", [V(x)]) => 0x800E | xy(*x, *x),
+            ("SNE", [V(x), V(y)]) => 0x9000 | xy(*x, *y),
+            ("LD", [I, Value(target)]) => 0xA000 | addr(target)?,
+            ("LD", [I, Long(_)]) => 0xF000,
+            ("RND", [V(x), Value(nn)]) => 0xC000 | (*x as u16) << 8 | byte(nn)?,
+            ("DRW", [V(x), V(y), Value(n)]) => 0xD000 | xy(*x, *y) | nibble(n)?,
+            ("SKP", [V(x)]) => 0xE09E | (*x as u16) << 8,
+            ("SKNP", [V(x)]) => 0xE0A1 | (*x as u16) << 8,
+            ("PLANE", [Value(n)]) => 0xF001 | nibble(n)? << 8,
+            ("AUDIO", []) => 0xF002,
+            ("PITCH", [V(x)]) => fx(*x, 0x3A),
+            ("LD", [V(x), Dt]) => fx(*x, 0x07),
+            ("LD", [V(x), K]) => fx(*x, 0x0A),
+            ("LD", [Dt, V(x)]) => fx(*x, 0x15),
+            ("LD", [St, V(x)]) => fx(*x, 0x18),
+            ("ADD", [I, V(x)]) => fx(*x, 0x1E),
+            ("LD", [F, V(x)]) => fx(*x, 0x29),
+            ("LD", [Hf, V(x)]) => fx(*x, 0x30),
+            ("LD", [B, V(x)]) => fx(*x, 0x33),
+            ("LD", [IndirectI, V(x)]) => fx(*x, 0x55),
+            ("LD", [V(x), IndirectI]) => fx(*x, 0x65),
+            ("LD", [R, V(x)]) => fx(*x, 0x75),
+            ("LD", [V(x), R]) => fx(*x, 0x85),
+            _ if is_mnemonic(mnemonic) => {
+                return Err(line.error(format!("invalid operands for `{}`", mnemonic)))
+            }
+            _ => return Err(line.error(format!("unknown instruction `{}`", mnemonic))),
+        };
+
+        Ok(opcode)
+    }
+
+    /// Evaluates an expression and checks it lies in `0..=max`.
+    fn value(&self, line: &Line, expression: &str, max: u16) -> Result<u16, AsmError> {
+        let value = self.evaluate(line, expression)?;
+        u16::try_from(value)
+            .ok()
+            .filter(|value| *value <= max)
+            .ok_or_else(|| {
+                line.error(format!(
+                    "`{}` is {}, out of range 0 to {:#X}",
+                    expression, value, max
+                ))
+            })
+    }
+
+    /// Evaluates terms joined by `+` and `-`.
+    fn evaluate(&self, line: &Line, expression: &str) -> Result<i64, AsmError> {
+        let mut total = 0;
+        let mut sign = 1;
+        let mut term = String::new();
+
+        for character in expression.chars().chain(std::iter::once('+')) {
+            match character {
+                '+' | '-' if !term.trim().is_empty() => {
+                    total += sign * self.term(line, term.trim())?;
+                    term.clear();
+                    sign = if character == '-' { -1 } else { 1 };
+                }
+                '-' if term.trim().is_empty() => sign = -sign,
+                '+' if term.trim().is_empty() => {}
+                _ => term.push(character),
+            }
+        }
+
+        Ok(total)
+    }
+
+    fn term(&self, line: &Line, term: &str) -> Result<i64, AsmError> {
+        let lowercase = term.to_ascii_lowercase();
+        let number = if let Some(hex) = lowercase.strip_prefix("0x") {
+            i64::from_str_radix(hex, 16).ok()
+        } else if let Some(binary) = lowercase.strip_prefix("0b") {
+            i64::from_str_radix(binary, 2).ok()
+        } else if term.starts_with(|c: char| c.is_ascii_digit()) {
+            term.parse().ok()
+        } else {
+            let value = self.symbols.get(term).copied();
+            return value.ok_or_else(|| line.error(format!("undefined symbol `{}`", term)));
+        };
+
+        number.ok_or_else(|| line.error(format!("invalid number `{}`", term)))
+    }
+}
+
+/// Number of bytes a statement assembles to.
+fn size(statement: &str) -> usize {
+    let (mnemonic, operands) = split_statement(statement);
+
+    match mnemonic.to_ascii_uppercase().as_str() {
+        "DB" => operands.len(),
+        "DW" => operands.len() * 2,
+        "LD" if operands.len() == 2
+            && operands[0].eq_ignore_ascii_case("I")
+            && long_operand(operands[1]).is_some() =>
+        {
+            4
+        }
+        _ => 2,
+    }
+}
+
+/// An instruction operand.
+enum Operand<'a> {
+    V(u8),
+    I,
+    IndirectI,
+    Dt,
+    St,
+    K,
+    F,
+    Hf,
+    B,
+    R,
+    Long(&'a str),
+    Value(&'a str),
+}
+
+impl<'a> Operand<'a> {
+    fn parse(operand: &'a str) -> Self {
+        if let Some(expression) = long_operand(operand) {
+            return Self::Long(expression);
+        }
+
+        match operand.to_ascii_uppercase().as_str() {
+            "I" => Self::I,
+            "[I]" => Self::IndirectI,
+            "DT" => Self::Dt,
+            "ST" => Self::St,
+            "K" => Self::K,
+            "F" => Self::F,
+            "HF" => Self::Hf,
+            "B" => Self::B,
+            "R" => Self::R,
+            register => match register.strip_prefix('V') {
+                Some(digit) if digit.len() == 1 => match u8::from_str_radix(digit, 16) {
+                    Ok(index) => Self::V(index),
+                    Err(_) => Self::Value(operand),
+                },
+                _ => Self::Value(operand),
+            },
+        }
+    }
+}
+
+fn long_operand(operand: &str) -> Option<&str> {
+    directive(operand, "long")
+}
+
+fn is_mnemonic(mnemonic: &str) -> bool {
+    const MNEMONICS: [&str; 31] = [
+        "CLS", "RET", "SCD", "SCU", "SCR", "SCL", "EXIT", "LOW", "HIGH", "JP", "CALL", "SE", "SNE",
+        "SAVE", "LOAD", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SHR", "SUBN", "SHL", "RND", "DRW",
+        "SKP", "SKNP", "PLANE", "AUDIO", "PITCH",
+    ];
+    MNEMONICS.contains(&mnemonic)
+}
+
+/// Splits a leading `name:` label from the rest of the line.
+fn label(text: &str) -> Option<(&str, &str)> {
+    let (name, rest) = text.split_once(':')?;
+    let name = name.trim();
+    is_identifier(name).then(|| (name, rest.trim()))
+}
+
+/// Splits a `NAME EQU value` constant definition.
+fn constant(statement: &str) -> Option<(&str, &str)> {
+    let (name, rest) = statement.split_once(char::is_whitespace)?;
+    let value = directive(rest.trim(), "equ")?;
+    Some((name, value))
+}
+
+fn split_statement(statement: &str) -> (&str, Vec<&str>) {
+    match statement.split_once(char::is_whitespace) {
+        Some((mnemonic, operands)) => (mnemonic, operands.split(',').map(str::trim).collect()),
+        None => (statement, Vec::new()),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut characters = name.chars();
+    characters
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && characters.all(|character| character.is_ascii_alphanumeric() || character == '_')
+}
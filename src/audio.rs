@@ -0,0 +1,131 @@
+//! Audio output for the buzzer driven by the sound timer.
+
+use std::io::{self, Write};
+
+/// Frequency of the buzzer tone in Hz.
+pub const BEEP_FREQUENCY: f32 = 440.0;
+
+/// Sample rate used by the bundled sinks in Hz.
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// Pitch at which an XO-CHIP pattern plays back at 4000 bits per second.
+pub const DEFAULT_PITCH: u8 = 64;
+
+/// What the buzzer plays during a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Silent,
+    /// The classic fixed-pitch tone.
+    Beep,
+    /// An XO-CHIP 1-bit sample loop loaded with `F002`.
+    Pattern(AudioPattern),
+}
+
+/// A 128-bit XO-CHIP audio pattern and its `FX3A` pitch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioPattern {
+    pub buffer: [u8; 16],
+    pub pitch: u8,
+}
+
+impl AudioPattern {
+    /// Playback rate in bits per second.
+    pub fn playback_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+
+    /// The bit at `index` (0-127), most significant bit of the first byte first.
+    pub fn bit(&self, index: usize) -> bool {
+        self.buffer[index / 8] >> (7 - index % 8) & 1 == 1
+    }
+}
+
+/// Turns a [`Sound`] into samples in the range -1.0 to 1.0.
+#[derive(Debug, Clone, Default)]
+pub struct Oscillator {
+    phase: f32,
+}
+
+impl Oscillator {
+    pub fn next_sample(&mut self, sound: &Sound, sample_rate: f32) -> f32 {
+        match sound {
+            Sound::Silent => 0.0,
+            Sound::Beep => {
+                self.phase = (self.phase + BEEP_FREQUENCY / sample_rate) % 1.0;
+                if self.phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Sound::Pattern(pattern) => {
+                self.phase = (self.phase + pattern.playback_rate() / sample_rate) % 128.0;
+                if pattern.bit(self.phase as usize) {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+        }
+    }
+}
+
+/// Receives the buzzer state once per 60 Hz frame.
+pub trait AudioSink {
+    fn update(&mut self, sound: Sound);
+}
+
+/// Discards all audio.
+#[derive(Debug, Default)]
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn update(&mut self, _sound: Sound) {}
+}
+
+/// Renders the buzzer into an in-memory 8-bit mono WAV.
+#[derive(Debug, Default)]
+pub struct WavSink {
+    samples: Vec<u8>,
+    oscillator: Oscillator,
+}
+
+impl WavSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the recorded audio as a RIFF/WAVE file.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        let data_size = self.samples.len() as u32;
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(36 + data_size).to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        // PCM, 1 channel, 1 byte per sample
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&SAMPLE_RATE.to_le_bytes())?;
+        writer.write_all(&SAMPLE_RATE.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&8u16.to_le_bytes())?;
+
+        writer.write_all(b"data")?;
+        writer.write_all(&data_size.to_le_bytes())?;
+        writer.write_all(&self.samples)
+    }
+}
+
+impl AudioSink for WavSink {
+    fn update(&mut self, sound: Sound) {
+        let samples_per_frame = SAMPLE_RATE / 60;
+
+        for _ in 0..samples_per_frame {
+            let sample = self.oscillator.next_sample(&sound, SAMPLE_RATE as f32);
+            self.samples.push((128.0 + sample * 64.0) as u8);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Runs a ROM without a window and dumps the final display and registers,
+//! or steps through it in an interactive debugger with `--debug`.
+//!
+//! Exits with 0 on success, 1 if the program faulted and 2 on usage or I/O
+//! errors.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+
+use chip8::audio::{AudioSink, NullSink, WavSink};
+use chip8::headless::{self, KeyScript, RunLimit};
+use chip8::movie::Movie;
+use chip8::romdb::RomDatabase;
+use chip8::trace::{TraceFilter, Tracer};
+use chip8::{Machine, Quirks, Rom};
+
+mod repl;
+#[path = "../chip8/tools.rs"]
+mod tools;
+
+const USAGE: &str = "Usage: chip8-headless <rom> [options]
+       chip8-headless disasm <rom>
+       chip8-headless asm <source> [output]
+
+Options:
+  --frames <n>       Run for n frames (default 600, or the movie length)
+  --cycles <n>       Run for n instructions instead of a number of frames
+  --keys <file>      Key script with one `<frame> <keys>` pair per line
+  --movie <file>     Replay a movie, using its quirks, speed, seed and keys
+  --record <file>    Write the run to a movie file
+  --format <format>  Display dump format: ascii (default) or pbm
+  --output <file>    Write the display dump to a file instead of stdout
+  --wav <file>       Record the buzzer to a WAV file
+  --seed <n>         Seed the random number generator used by CXNN
+  --quirks <preset>  Quirk profile: cosmac-vip, chip-48, schip or xo-chip
+  --ipf <n>          Instructions per frame (default 10)
+  --load-state <file>
+                     Restore a save state before running
+  --save-state <file>
+                     Write a save state after running
+  --trace <file>     Write a trace record for every instruction to a file
+  --trace-range <a-b>
+                     Only trace instructions at hex addresses a to b
+  --trace-ops <list> Only trace opcode families, e.g. `D,8,F`
+  --debug            Start an interactive debugger instead of running
+
+Quirks and speed default to the ROM's entry in the ROM database, if it has
+one.";
+
+const DEFAULT_FRAMES: u64 = 600;
+
+enum Format {
+    Ascii,
+    Pbm,
+}
+
+struct Options {
+    rom_path: String,
+    limit: Option<RunLimit>,
+    keys_path: Option<String>,
+    movie_path: Option<String>,
+    record_path: Option<String>,
+    format: Format,
+    output_path: Option<String>,
+    wav_path: Option<String>,
+    seed: Option<u64>,
+    quirks: Option<Quirks>,
+    instructions_per_frame: Option<usize>,
+    load_state_path: Option<String>,
+    save_state_path: Option<String>,
+    trace_path: Option<String>,
+    trace_filter: TraceFilter,
+    debug: bool,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    // Subcommands that work on files instead of running a ROM
+    match args.first().map(String::as_str) {
+        Some("disasm") => return tools::disasm("chip8-headless", &args[1..]),
+        Some("asm") => return tools::asm("chip8-headless", &args[1..]),
+        _ => {}
+    }
+
+    let options = match parse_args(args.into_iter()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("Error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let rom = Rom::build(&options.rom_path).unwrap_or_else(|err| {
+        eprintln!("Error reading {}: {}", options.rom_path, err);
+        process::exit(2);
+    });
+
+    let mut script = match &options.keys_path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| KeyScript::parse(&source))
+            .unwrap_or_else(|err| {
+                eprintln!("Error reading {}: {}", path, err);
+                process::exit(2);
+            }),
+        None => KeyScript::new(),
+    };
+
+    let database = RomDatabase::load_default().unwrap_or_else(|err| {
+        eprintln!("Error reading ROM database: {}", err);
+        process::exit(2);
+    });
+    let entry = database.get(&rom.sha1()).cloned().unwrap_or_default();
+
+    // Always seed explicitly so a recorded movie knows the seed
+    let mut seed = options.seed.unwrap_or_else(rand::random);
+    let mut machine = Machine::new(options.quirks.or(entry.quirks).unwrap_or_default());
+    machine.seed_rng(seed);
+    if let Some(instructions) = options
+        .instructions_per_frame
+        .or(entry.instructions_per_frame)
+    {
+        machine.set_instructions_per_frame(instructions);
+    }
+    if let Err(err) = machine.load_rom(rom) {
+        eprintln!("Error loading ROM: {}", err);
+        process::exit(2);
+    }
+
+    let mut limit = options.limit.unwrap_or(RunLimit::Frames(DEFAULT_FRAMES));
+    if let Some(path) = &options.movie_path {
+        let movie = fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| Movie::parse(&source))
+            .and_then(|movie| movie.apply(&mut machine).map(|_| movie))
+            .unwrap_or_else(|err| {
+                eprintln!("Error replaying {}: {}", path, err);
+                process::exit(2);
+            });
+        limit = options.limit.unwrap_or(RunLimit::Frames(movie.frames));
+        seed = movie.seed;
+        script = movie.keys;
+    }
+
+    if let Some(path) = &options.load_state_path {
+        let result = fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|state| machine.load_state(&state).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            eprintln!("Error loading {}: {}", path, err);
+            process::exit(2);
+        }
+    }
+
+    if let Some(path) = &options.trace_path {
+        match Tracer::create(path, options.trace_filter.clone()) {
+            Ok(tracer) => machine.set_tracer(Some(tracer)),
+            Err(err) => {
+                eprintln!("Error creating {}: {}", path, err);
+                process::exit(2);
+            }
+        }
+    }
+
+    if options.debug {
+        if let Err(err) = repl::run(&mut machine, io::stdin().lock(), io::stdout()) {
+            eprintln!("Error: {}", err);
+            process::exit(2);
+        }
+        finish_trace(&mut machine, &options);
+        return;
+    }
+
+    let mut wav = WavSink::new();
+    let audio: &mut dyn AudioSink = match options.wav_path {
+        Some(_) => &mut wav,
+        None => &mut NullSink,
+    };
+    let result = headless::run(&mut machine, limit, &script, audio);
+    finish_trace(&mut machine, &options);
+
+    if let Some(path) = &options.record_path {
+        let mut movie = Movie::new(machine.rom_hash(), seed, *machine.quirks());
+        movie.instructions_per_frame = machine.instructions_per_frame();
+        movie.frames = machine.frames();
+        movie.keys = script;
+        movie.keys.truncate(movie.frames);
+        if let Err(err) = fs::write(path, movie.to_string()) {
+            eprintln!("Error writing {}: {}", path, err);
+            process::exit(2);
+        }
+    }
+
+    if let Some(path) = &options.wav_path {
+        if let Err(err) = fs::File::create(path).and_then(|file| wav.write_to(file)) {
+            eprintln!("Error writing {}: {}", path, err);
+            process::exit(2);
+        }
+    }
+
+    if let Some(path) = &options.save_state_path {
+        if let Err(err) = fs::write(path, machine.save_state()) {
+            eprintln!("Error writing {}: {}", path, err);
+            process::exit(2);
+        }
+    }
+
+    let dump = match options.format {
+        Format::Ascii => headless::display_to_ascii(machine.ppu()),
+        Format::Pbm => headless::display_to_pbm(machine.ppu()),
+    };
+    match &options.output_path {
+        Some(path) => {
+            if let Err(err) = fs::write(path, dump) {
+                eprintln!("Error writing {}: {}", path, err);
+                process::exit(2);
+            }
+        }
+        None => print!("{}", dump),
+    }
+    print!("{}", headless::registers_to_string(&machine));
+
+    match result {
+        Ok(cycles) => println!("Executed {} instructions.", cycles),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut rom_path = None;
+    let mut limit = None;
+    let mut keys_path = None;
+    let mut movie_path = None;
+    let mut record_path = None;
+    let mut format = Format::Ascii;
+    let mut output_path = None;
+    let mut wav_path = None;
+    let mut seed = None;
+    let mut quirks = None;
+    let mut instructions_per_frame = None;
+    let mut load_state_path = None;
+    let mut save_state_path = None;
+    let mut trace_path = None;
+    let mut trace_filter = TraceFilter::new();
+    let mut debug = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frames" => limit = Some(RunLimit::Frames(parse_number(&arg, args.next())?)),
+            "--cycles" => limit = Some(RunLimit::Cycles(parse_number(&arg, args.next())?)),
+            "--keys" => keys_path = Some(value(&arg, args.next())?),
+            "--movie" => movie_path = Some(value(&arg, args.next())?),
+            "--record" => record_path = Some(value(&arg, args.next())?),
+            "--format" => {
+                format = match value(&arg, args.next())?.as_str() {
+                    "ascii" => Format::Ascii,
+                    "pbm" => Format::Pbm,
+                    other => return Err(format!("unknown format `{}`", other)),
+                }
+            }
+            "--output" => output_path = Some(value(&arg, args.next())?),
+            "--wav" => wav_path = Some(value(&arg, args.next())?),
+            "--seed" => seed = Some(parse_number(&arg, args.next())?),
+            "--quirks" => {
+                let name = value(&arg, args.next())?;
+                quirks = Some(
+                    Quirks::from_name(&name)
+                        .ok_or_else(|| format!("unknown quirk preset `{}`", name))?,
+                );
+            }
+            "--ipf" => {
+                let instructions = parse_number(&arg, args.next())?;
+                if instructions == 0 {
+                    return Err("`--ipf` must be at least 1".to_string());
+                }
+                instructions_per_frame = Some(instructions as usize);
+            }
+            "--load-state" => load_state_path = Some(value(&arg, args.next())?),
+            "--save-state" => save_state_path = Some(value(&arg, args.next())?),
+            "--trace" => trace_path = Some(value(&arg, args.next())?),
+            "--trace-range" => {
+                let range = value(&arg, args.next())?;
+                trace_filter.addresses = Some(TraceFilter::parse_addresses(&range)?);
+            }
+            "--trace-ops" => {
+                let families = value(&arg, args.next())?;
+                trace_filter.families = Some(TraceFilter::parse_families(&families)?);
+            }
+            "--debug" => debug = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            _ if rom_path.is_none() => rom_path = Some(arg),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    if movie_path.is_some() && keys_path.is_some() {
+        return Err("`--movie` and `--keys` cannot be combined".to_string());
+    }
+    // Movies start from power-on, not from a save state
+    if (movie_path.is_some() || record_path.is_some()) && load_state_path.is_some() {
+        return Err("movies cannot be combined with `--load-state`".to_string());
+    }
+
+    Ok(Options {
+        rom_path: rom_path.ok_or("no ROM path provided")?,
+        limit,
+        keys_path,
+        movie_path,
+        record_path,
+        format,
+        output_path,
+        wav_path,
+        seed,
+        quirks,
+        instructions_per_frame,
+        load_state_path,
+        save_state_path,
+        trace_path,
+        trace_filter,
+        debug,
+    })
+}
+
+fn finish_trace(machine: &mut Machine, options: &Options) {
+    if let (Some(tracer), Some(path)) = (machine.take_tracer(), &options.trace_path) {
+        if let Err(err) = tracer.finish() {
+            eprintln!("Error writing {}: {}", path, err);
+            process::exit(2);
+        }
+    }
+}
+
+fn value(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("`{}` expects a value", flag))
+}
+
+fn parse_number(flag: &str, number: Option<String>) -> Result<u64, String> {
+    let number = value(flag, number)?;
+    number
+        .parse()
+        .map_err(|_| format!("`{}` expects a number, got `{}`", flag, number))
+}
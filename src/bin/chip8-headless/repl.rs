@@ -0,0 +1,225 @@
+//! Terminal front end for [`Debugger`].
+
+use std::io::{self, BufRead, Write};
+
+use chip8::debugger::{Breakpoint, Debugger, StopReason, Watchpoint};
+use chip8::headless;
+use chip8::Machine;
+
+const HELP: &str = "Commands:
+  s, step [n]            Execute n instructions (default 1)
+  c, continue [n]        Run until a breakpoint or watchpoint, at most n
+                         instructions (default 1000000)
+  b, break <addr>        Break when PC reaches addr
+  b, break op <pattern>  Break on an opcode, `?` matches any digit (e.g. D??F)
+  w, watch <addr>        Stop when the byte at addr changes
+  w, watch v<x>          Stop when register Vx changes
+  d, delete b|w <n>      Delete breakpoint or watchpoint n
+  l, list                List breakpoints and watchpoints
+  r, regs                Show PC, I, V0-VF and the stack
+  m, mem <addr> [len]    Dump len bytes of memory (default 16)
+  k, key <keys|->        Hold the given hex keys (e.g. 46), or release all with -
+  display                Show the display
+  h, help                Show this help
+  q, quit                Exit the debugger
+
+Addresses are hexadecimal.";
+
+const DEFAULT_CONTINUE_LIMIT: u64 = 1_000_000;
+
+/// Reads commands from `input` until `quit` or end of input.
+pub fn run(machine: &mut Machine, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut debugger = Debugger::new();
+
+    show_position(machine, &mut output)?;
+    write!(output, "(chip8) ")?;
+    output.flush()?;
+
+    for line in input.lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        if let Some((&command, args)) = words.split_first() {
+            if matches!(command, "q" | "quit") {
+                break;
+            }
+            if let Err(message) = execute(command, args, machine, &mut debugger, &mut output) {
+                writeln!(output, "Error: {}", message)?;
+            }
+        }
+
+        write!(output, "(chip8) ")?;
+        output.flush()?;
+    }
+
+    writeln!(output)
+}
+
+fn execute(
+    command: &str,
+    args: &[&str],
+    machine: &mut Machine,
+    debugger: &mut Debugger,
+    output: &mut impl Write,
+) -> Result<(), String> {
+    match (command, args) {
+        ("s" | "step" | "c" | "continue", _) => {
+            let default = if command.starts_with('s') {
+                1
+            } else {
+                DEFAULT_CONTINUE_LIMIT
+            };
+            let reason = debugger.run(machine, count_arg(args, default)?);
+            show_stop(reason, debugger, output)
+                .and_then(|_| show_position(machine, output))
+                .map_err(|err| err.to_string())?;
+        }
+        ("b" | "break", ["op", pattern]) => {
+            let breakpoint = Breakpoint::opcode_pattern(pattern)
+                .ok_or_else(|| format!("invalid opcode pattern `{}`", pattern))?;
+            let index = debugger.add_breakpoint(breakpoint);
+            let _ = writeln!(output, "Breakpoint {} on opcode {}", index, pattern);
+        }
+        ("b" | "break", [address]) => {
+            let address = parse_address(address)?;
+            let index = debugger.add_breakpoint(Breakpoint::Address(address));
+            let _ = writeln!(output, "Breakpoint {} at {:#06X}", index, address);
+        }
+        ("w" | "watch", [target]) => {
+            let watchpoint = match target.strip_prefix(['v', 'V']) {
+                Some(register) => u8::from_str_radix(register, 16)
+                    .ok()
+                    .filter(|register| *register < 16)
+                    .map(Watchpoint::Register)
+                    .ok_or_else(|| format!("invalid register `{}`", target))?,
+                None => Watchpoint::Memory(parse_address(target)?),
+            };
+            let index = debugger.add_watchpoint(watchpoint);
+            let _ = writeln!(
+                output,
+                "Watchpoint {} on {}",
+                index,
+                describe_watch(&watchpoint)
+            );
+        }
+        ("d" | "delete", [kind, index]) => {
+            let index: usize = index
+                .parse()
+                .map_err(|_| format!("invalid index `{}`", index))?;
+            let removed = match *kind {
+                "b" | "break" => debugger.remove_breakpoint(index).is_some(),
+                "w" | "watch" => debugger.remove_watchpoint(index).is_some(),
+                other => return Err(format!("expected `b` or `w`, got `{}`", other)),
+            };
+            if !removed {
+                return Err(format!("no {} {}", kind, index));
+            }
+        }
+        ("l" | "list", []) => {
+            for (index, breakpoint) in debugger.breakpoints().iter().enumerate() {
+                let _ = match breakpoint {
+                    Breakpoint::Address(address) => {
+                        writeln!(output, "Breakpoint {}: {:#06X}", index, address)
+                    }
+                    Breakpoint::Opcode { value, mask } => writeln!(
+                        output,
+                        "Breakpoint {}: opcode {:04X} mask {:04X}",
+                        index, value, mask
+                    ),
+                };
+            }
+            for (index, watchpoint) in debugger.watchpoints().iter().enumerate() {
+                let _ = writeln!(
+                    output,
+                    "Watchpoint {}: {}",
+                    index,
+                    describe_watch(watchpoint)
+                );
+            }
+        }
+        ("r" | "regs", []) => {
+            let _ = write!(output, "{}", headless::registers_to_string(machine));
+            let _ = writeln!(output, "Cycles: {}", debugger.cycles());
+        }
+        ("m" | "mem", [address, rest @ ..]) => {
+            let address = parse_address(address)?;
+            let length = count_arg(rest, 16)?;
+            dump_memory(machine, address, length, output);
+        }
+        ("k" | "key", [keys]) => {
+            let keys =
+                headless::parse_keys(keys).ok_or_else(|| format!("invalid keys `{}`", keys))?;
+            machine.set_keys(keys);
+        }
+        ("display", []) => {
+            let _ = write!(output, "{}", headless::display_to_ascii(machine.ppu()));
+        }
+        ("h" | "help", _) => {
+            let _ = writeln!(output, "{}", HELP);
+        }
+        _ => return Err(format!("unknown command `{}`, try `help`", command)),
+    }
+
+    Ok(())
+}
+
+fn show_stop(reason: StopReason, debugger: &Debugger, output: &mut impl Write) -> io::Result<()> {
+    match reason {
+        StopReason::Done => Ok(()),
+        StopReason::Breakpoint(index) => writeln!(output, "Hit breakpoint {}", index),
+        StopReason::Watchpoint { index, old, new } => writeln!(
+            output,
+            "Watchpoint {} ({}): {:#04X} -> {:#04X}",
+            index,
+            describe_watch(&debugger.watchpoints()[index]),
+            old,
+            new
+        ),
+        StopReason::Error(err) => writeln!(output, "Error: {}", err),
+        StopReason::Exited => writeln!(output, "Program exited"),
+    }
+}
+
+fn show_position(machine: &Machine, output: &mut impl Write) -> io::Result<()> {
+    let pc = machine.cpu().program_counter.get_value();
+    match machine.ram().get_instruction(pc) {
+        Ok(opcode) => writeln!(output, "{:#06X}: {:04X}", pc, opcode),
+        Err(_) => writeln!(output, "{:#06X}: ????", pc),
+    }
+}
+
+fn dump_memory(machine: &Machine, address: u16, length: u64, output: &mut impl Write) {
+    let end = (address as u64)
+        .saturating_add(length)
+        .min(machine.ram().size() as u64);
+
+    for row in (address as u64..end).step_by(8) {
+        let bytes: Vec<String> = (row..end.min(row + 8))
+            .filter_map(|address| machine.ram().read_byte(address as u16).ok())
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        let _ = writeln!(output, "{:#06X}: {}", row, bytes.join(" "));
+    }
+}
+
+fn describe_watch(watchpoint: &Watchpoint) -> String {
+    match watchpoint {
+        Watchpoint::Memory(address) => format!("memory {:#06X}", address),
+        Watchpoint::Register(index) => format!("V{:X}", index),
+    }
+}
+
+fn parse_address(address: &str) -> Result<u16, String> {
+    let digits = address.trim_start_matches("0x").trim_start_matches("0X");
+    u16::from_str_radix(digits, 16).map_err(|_| format!("invalid address `{}`", address))
+}
+
+fn count_arg(args: &[&str], default: u64) -> Result<u64, String> {
+    match args {
+        [] => Ok(default),
+        [count] => count
+            .parse()
+            .map_err(|_| format!("expected a number, got `{}`", count)),
+        _ => Err("too many arguments".to_string()),
+    }
+}
@@ -0,0 +1,57 @@
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::Sdl;
+
+use chip8::audio::{AudioSink, Oscillator, Sound, SAMPLE_RATE};
+
+const VOLUME: f32 = 0.25;
+
+struct Voice {
+    oscillator: Oscillator,
+    sound: Sound,
+    sample_rate: f32,
+}
+
+impl AudioCallback for Voice {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = self.oscillator.next_sample(&self.sound, self.sample_rate) * VOLUME;
+        }
+    }
+}
+
+/// Plays the buzzer through the default SDL audio device.
+pub struct SdlAudio {
+    device: AudioDevice<Voice>,
+}
+
+impl SdlAudio {
+    pub fn new(sdl_context: &Sdl) -> Result<Self, String> {
+        let audio_subsystem = sdl_context.audio()?;
+        let spec = AudioSpecDesired {
+            freq: Some(SAMPLE_RATE as i32),
+            channels: Some(1),
+            samples: None,
+        };
+
+        let device = audio_subsystem.open_playback(None, &spec, |spec| Voice {
+            oscillator: Oscillator::default(),
+            sound: Sound::Silent,
+            sample_rate: spec.freq as f32,
+        })?;
+
+        Ok(Self { device })
+    }
+}
+
+impl AudioSink for SdlAudio {
+    fn update(&mut self, sound: Sound) {
+        if sound == Sound::Silent {
+            self.device.pause();
+        } else {
+            self.device.lock().sound = sound;
+            self.device.resume();
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Command-line options for running a ROM.
+
+use std::path::Path;
+
+use chip8::palette::Palette;
+use chip8::viewport::Scaling;
+use chip8::Quirks;
+
+pub const USAGE: &str = "Usage: chip8 [options] --rom <path>
+       chip8 disasm <rom>
+       chip8 asm <source> [output]
+
+Options:
+  --rom <path>       ROM to run; a bare name that is not a file is looked up
+                     in roms/
+  --quirks <preset>  Quirk profile: cosmac-vip, chip-48, schip or xo-chip
+  --ipf <n>          Instructions per frame (default 10)
+  --hz <n>           Instructions per second, instead of --ipf
+  --scale <n>        Initial window size as a multiple of 64x32 (default 10)
+  --scaling <mode>   Fit the display to the window: aspect (default) keeps
+                     its shape, integer also keeps pixels equally sized,
+                     stretch fills the window
+  --fullscreen       Start in fullscreen; F11 toggles it
+  --palette <colours>
+                     A theme (classic, amber, green, lcd or octo), or 2 or 4
+                     comma-separated RRGGBB colours for background,
+                     foreground and the XO-CHIP planes
+  --headless         Run without a window and print the final display
+  --frames <n>       Frames to run with --headless (default 600, or the
+                     replay length)
+  --seed <n>         Seed the random number generator used by CXNN
+  --trace <file>     Write a trace record for every instruction to a file
+  --record <file>    Record a movie of the session
+  --replay <file>    Replay a movie
+  -h, --help         Show this help
+
+Quirks, speed, palette and extra key bindings default to the ROM's entry in
+the ROM database, if it has one.";
+
+const DEFAULT_SCALE: u32 = 10;
+pub const DEFAULT_FRAMES: u64 = 600;
+
+pub struct Options {
+    pub rom_path: String,
+    pub quirks: Option<Quirks>,
+    pub instructions_per_frame: Option<usize>,
+    pub scale: u32,
+    pub scaling: Option<Scaling>,
+    pub fullscreen: bool,
+    pub palette: Option<Palette>,
+    pub headless: bool,
+    /// Frames to run headless; defaults to the replay length or
+    /// [`DEFAULT_FRAMES`].
+    pub frames: Option<u64>,
+    pub seed: Option<u64>,
+    pub trace_path: Option<String>,
+    pub record_path: Option<String>,
+    pub replay_path: Option<String>,
+}
+
+/// Returns `Ok(None)` if help was requested.
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut rom_path = None;
+    let mut quirks = None;
+    let mut instructions_per_frame = None;
+    let mut scale = DEFAULT_SCALE;
+    let mut scaling = None;
+    let mut fullscreen = false;
+    let mut palette = None;
+    let mut headless = false;
+    let mut frames = None;
+    let mut seed = None;
+    let mut trace_path = None;
+    let mut record_path = None;
+    let mut replay_path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--rom" => rom_path = Some(value(&arg, args.next())?),
+            "--quirks" => {
+                let name = value(&arg, args.next())?;
+                quirks = Some(Quirks::from_name(&name).ok_or_else(|| {
+                    format!(
+                        "unknown quirk preset `{}`, expected one of {}",
+                        name,
+                        Quirks::PRESET_NAMES.join(", ")
+                    )
+                })?);
+            }
+            "--ipf" => {
+                instructions_per_frame = Some(parse_positive(&arg, args.next())? as usize);
+            }
+            "--hz" => {
+                // Rounded to whole instructions per 60 Hz frame
+                let hz = parse_positive(&arg, args.next())?;
+                instructions_per_frame = Some(((hz + 30) / 60).max(1) as usize);
+            }
+            "--scale" => {
+                scale = u32::try_from(parse_positive(&arg, args.next())?)
+                    .ok()
+                    .filter(|scale| *scale <= 64)
+                    .ok_or("`--scale` must be between 1 and 64")?;
+            }
+            "--scaling" => {
+                let name = value(&arg, args.next())?;
+                scaling = Some(Scaling::from_name(&name).ok_or_else(|| {
+                    format!(
+                        "unknown scaling `{}`, expected one of {}",
+                        name,
+                        Scaling::NAMES.join(", ")
+                    )
+                })?);
+            }
+            "--fullscreen" => fullscreen = true,
+            "--palette" => palette = Some(Palette::parse(&value(&arg, args.next())?)?),
+            "--headless" => headless = true,
+            "--frames" => frames = Some(parse_number(&arg, args.next())?),
+            "--seed" => seed = Some(parse_number(&arg, args.next())?),
+            "--trace" => trace_path = Some(value(&arg, args.next())?),
+            "--record" => record_path = Some(value(&arg, args.next())?),
+            "--replay" => replay_path = Some(value(&arg, args.next())?),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ if rom_path.is_none() => rom_path = Some(rom_in_roms_dir(arg)),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    if frames.is_some() && !headless {
+        return Err("`--frames` only applies with `--headless`".to_string());
+    }
+
+    Ok(Some(Options {
+        rom_path: rom_path.ok_or("no ROM given, use `--rom <path>`")?,
+        quirks,
+        instructions_per_frame,
+        scale,
+        scaling,
+        fullscreen,
+        palette,
+        headless,
+        frames,
+        seed,
+        trace_path,
+        record_path,
+        replay_path,
+    }))
+}
+
+/// Older versions took a ROM name relative to `roms/` as the first argument;
+/// keep accepting that when no such file exists.
+fn rom_in_roms_dir(name: String) -> String {
+    let bundled = Path::new("roms").join(&name);
+    if !Path::new(&name).is_file() && bundled.is_file() {
+        bundled.to_string_lossy().into_owned()
+    } else {
+        name
+    }
+}
+
+fn value(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("`{}` expects a value", flag))
+}
+
+fn parse_number(flag: &str, number: Option<String>) -> Result<u64, String> {
+    let number = value(flag, number)?;
+    number
+        .parse()
+        .map_err(|_| format!("`{}` expects a number, got `{}`", flag, number))
+}
+
+fn parse_positive(flag: &str, number: Option<String>) -> Result<u64, String> {
+    match parse_number(flag, number)? {
+        0 => Err(format!("`{}` must be at least 1", flag)),
+        number => Ok(number),
+    }
+}
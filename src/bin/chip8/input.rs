@@ -0,0 +1,235 @@
+use sdl2::controller::{Axis, GameController};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::{EventPump, GameControllerSubsystem};
+
+use chip8::keymap::KeyMap;
+use chip8::Machine;
+
+/// How far the left stick has to move before it counts as a D-pad press.
+const STICK_THRESHOLD: i16 = 16_000;
+
+pub struct Input {
+    pub escape_pressed: bool,
+    /// Slot to save to this frame (Shift+F1-F8).
+    pub save_slot: Option<u8>,
+    /// Slot to load from this frame (F1-F8).
+    pub load_slot: Option<u8>,
+    /// Backspace is held to rewind.
+    pub rewinding: bool,
+    /// `-` (slower) or `=` (faster) was pressed this frame.
+    pub speed_step: Option<bool>,
+    /// Tab is held to fast-forward.
+    pub fast_forward: bool,
+    /// Toggled with the backquote key.
+    pub slow_motion: bool,
+    /// F9 was pressed this frame to switch to the next palette.
+    pub next_palette: bool,
+    /// F11 was pressed this frame to switch fullscreen on or off.
+    pub toggle_fullscreen: bool,
+    /// Ignore the CHIP-8 keys, e.g. while a movie is replaying.
+    pub keypad_locked: bool,
+    keymap: KeyMap,
+    controller_map: KeyMap,
+    controller_subsystem: GameControllerSubsystem,
+    /// Connected controllers; SDL only sends their events while they are
+    /// open.
+    controllers: Vec<GameController>,
+    /// Controller buttons held down, by SDL name.
+    buttons_held: Vec<String>,
+    /// D-pad directions the left stick is pushed in.
+    stick_held: Vec<&'static str>,
+}
+
+impl Input {
+    pub fn new(
+        keymap: KeyMap,
+        controller_map: KeyMap,
+        controller_subsystem: GameControllerSubsystem,
+    ) -> Self {
+        Self {
+            escape_pressed: false,
+            save_slot: None,
+            load_slot: None,
+            rewinding: false,
+            speed_step: None,
+            fast_forward: false,
+            slow_motion: false,
+            next_palette: false,
+            toggle_fullscreen: false,
+            keypad_locked: false,
+            keymap,
+            controller_map,
+            controller_subsystem,
+            controllers: Vec::new(),
+            buttons_held: Vec::new(),
+            stick_held: Vec::new(),
+        }
+    }
+
+    pub fn poll(&mut self, event_pump: &mut EventPump, machine: &mut Machine) {
+        self.save_slot = None;
+        self.load_slot = None;
+        self.speed_step = None;
+        self.next_palette = false;
+        self.toggle_fullscreen = false;
+
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => self.escape_pressed = true,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                } => self.rewinding = true,
+                Event::KeyUp {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                } => self.rewinding = false,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Minus),
+                    ..
+                } => self.speed_step = Some(false),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Equals),
+                    ..
+                } => self.speed_step = Some(true),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Tab),
+                    ..
+                } => self.fast_forward = true,
+                Event::KeyUp {
+                    keycode: Some(Keycode::Tab),
+                    ..
+                } => self.fast_forward = false,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backquote),
+                    repeat: false,
+                    ..
+                } => self.slow_motion = !self.slow_motion,
+                Event::KeyDown {
+                    keycode: Some(Keycode::F9),
+                    repeat: false,
+                    ..
+                } => self.next_palette = true,
+                Event::KeyDown {
+                    keycode: Some(Keycode::F11),
+                    repeat: false,
+                    ..
+                } => self.toggle_fullscreen = true,
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    keymod,
+                    ..
+                } if slot_for(keycode).is_some() => {
+                    if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                        self.save_slot = slot_for(keycode);
+                    } else {
+                        self.load_slot = slot_for(keycode);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } if !self.keypad_locked => {
+                    if let Some(key) = self.keymap.key_for(&keycode.name()) {
+                        machine.press_key(key);
+                    }
+                }
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } if !self.keypad_locked => {
+                    if let Some(key) = self.keymap.key_for(&keycode.name()) {
+                        machine.release_key(key);
+                    }
+                }
+                // Also sent at startup for controllers that are already
+                // plugged in
+                Event::ControllerDeviceAdded { which, .. } => {
+                    match self.controller_subsystem.open(which) {
+                        Ok(controller) => self.controllers.push(controller),
+                        Err(err) => eprintln!("Error opening controller: {}", err),
+                    }
+                }
+                Event::ControllerDeviceRemoved { which, .. } => self
+                    .controllers
+                    .retain(|controller| controller.instance_id() != which),
+                Event::ControllerButtonDown { button, .. } if !self.keypad_locked => {
+                    self.set_button(machine, &button.string(), true)
+                }
+                Event::ControllerButtonUp { button, .. } if !self.keypad_locked => {
+                    self.set_button(machine, &button.string(), false)
+                }
+                Event::ControllerAxisMotion { axis, value, .. } if !self.keypad_locked => {
+                    let (negative, positive) = match axis {
+                        Axis::LeftX => ("dpleft", "dpright"),
+                        Axis::LeftY => ("dpup", "dpdown"),
+                        _ => continue,
+                    };
+                    self.set_stick(machine, negative, value < -STICK_THRESHOLD);
+                    self.set_stick(machine, positive, value > STICK_THRESHOLD);
+                }
+                _ => {}
+            };
+        }
+    }
+
+    fn set_button(&mut self, machine: &mut Machine, button: &str, pressed: bool) {
+        self.buttons_held.retain(|held| held != button);
+        if pressed {
+            self.buttons_held.push(button.to_string());
+        }
+        self.update_key(machine, button);
+    }
+
+    /// The stick reports every small movement, so only changes of direction
+    /// are passed on.
+    fn set_stick(&mut self, machine: &mut Machine, direction: &'static str, pushed: bool) {
+        if self.stick_held.contains(&direction) == pushed {
+            return;
+        }
+        self.stick_held.retain(|held| *held != direction);
+        if pushed {
+            self.stick_held.push(direction);
+        }
+        self.update_key(machine, direction);
+    }
+
+    /// Presses or releases the key `input` is bound to. The key stays held
+    /// while any button or stick direction bound to it still is, so letting
+    /// go of the stick does not release a key held on the D-pad.
+    fn update_key(&self, machine: &mut Machine, input: &str) {
+        let Some(key) = self.controller_map.key_for(input) else {
+            return;
+        };
+
+        let mut held = self
+            .buttons_held
+            .iter()
+            .map(String::as_str)
+            .chain(self.stick_held.iter().copied());
+        if held.any(|input| self.controller_map.key_for(input) == Some(key)) {
+            machine.press_key(key);
+        } else {
+            machine.release_key(key);
+        }
+    }
+}
+
+fn slot_for(keycode: Keycode) -> Option<u8> {
+    match keycode {
+        Keycode::F1 => Some(1),
+        Keycode::F2 => Some(2),
+        Keycode::F3 => Some(3),
+        Keycode::F4 => Some(4),
+        Keycode::F5 => Some(5),
+        Keycode::F6 => Some(6),
+        Keycode::F7 => Some(7),
+        Keycode::F8 => Some(8),
+        _ => None,
+    }
+}
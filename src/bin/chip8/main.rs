@@ -0,0 +1,393 @@
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::Instant;
+
+use chip8::audio::NullSink;
+use chip8::config::{Config, CONFIG_FILE};
+use chip8::headless::{self, KeyScript, RunLimit};
+use chip8::movie::Movie;
+use chip8::palette::Palette;
+use chip8::rewind::RewindBuffer;
+use chip8::romdb::RomDatabase;
+use chip8::scheduler::FrameScheduler;
+use chip8::trace::{TraceFilter, Tracer};
+use chip8::{Machine, Rom, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+mod audio;
+mod cli;
+mod input;
+mod tools;
+mod video;
+
+use audio::SdlAudio;
+use chip8::audio::{AudioSink, Sound};
+use cli::Options;
+use input::Input;
+use video::Screen;
+
+/// Speeds that the `-` and `=` hotkeys step through, in instructions per
+/// frame.
+const SPEED_STEPS: [usize; 14] = [1, 2, 3, 5, 7, 10, 15, 20, 30, 50, 100, 200, 500, 1000];
+/// How much faster than real time Tab fast-forwards.
+const FAST_FORWARD: f64 = 4.0;
+/// How much slower than real time slow motion runs.
+const SLOW_MOTION: f64 = 0.25;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    // Subcommands that work on files instead of running a ROM
+    match args.first().map(String::as_str) {
+        Some("disasm") => return tools::disasm("chip8", &args[1..]),
+        Some("asm") => return tools::asm("chip8", &args[1..]),
+        _ => {}
+    }
+
+    let options = match cli::parse_args(args.into_iter()) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("Error: {}\n\n{}", message, cli::USAGE);
+            process::exit(2);
+        }
+    };
+
+    let config = if Path::new(CONFIG_FILE).exists() {
+        Config::load(Path::new(CONFIG_FILE)).unwrap_or_else(|err| {
+            eprintln!("Error reading {}: {}", CONFIG_FILE, err);
+            process::exit(2);
+        })
+    } else {
+        Config::default()
+    };
+
+    let rom_path = &options.rom_path;
+    let rom_name = Path::new(rom_path)
+        .file_name()
+        .map_or(rom_path.clone(), |name| name.to_string_lossy().into_owned());
+
+    let rom = Rom::build(rom_path).unwrap_or_else(|err| {
+        eprintln!("Error reading {}: {}", rom_path, err);
+        process::exit(2);
+    });
+
+    // Command-line options win over the ROM database, which wins over the
+    // defaults
+    let database = RomDatabase::load_default().unwrap_or_else(|err| {
+        eprintln!("Error reading ROM database: {}", err);
+        process::exit(2);
+    });
+    let entry = database.get(&rom.sha1()).cloned().unwrap_or_default();
+    let quirks = options.quirks.or(entry.quirks).unwrap_or_default();
+    let palette = options
+        .palette
+        .or(entry.palette)
+        .or(config.palette)
+        .unwrap_or_default();
+    let keymap = config.keymap_for_entry(&rom_name, &entry);
+
+    // Always seed explicitly so a recorded movie knows the seed
+    let mut seed = options.seed.unwrap_or_else(rand::random);
+    let mut machine = Machine::new(quirks);
+    machine.seed_rng(seed);
+    if let Some(instructions) = options
+        .instructions_per_frame
+        .or(entry.instructions_per_frame)
+    {
+        machine.set_instructions_per_frame(instructions);
+    }
+    if let Err(err) = machine.load_rom(rom) {
+        eprintln!("Error loading ROM: {}", err);
+        process::exit(2);
+    }
+
+    let replay = options.replay_path.as_ref().map(|path| {
+        let movie = fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| Movie::parse(&source))
+            .and_then(|movie| movie.apply(&mut machine).map(|_| movie))
+            .unwrap_or_else(|err| {
+                eprintln!("Error replaying {}: {}", path, err);
+                process::exit(2);
+            });
+        seed = movie.seed;
+        movie
+    });
+    let mut recording = options.record_path.as_ref().map(|_| {
+        let mut movie = Movie::new(machine.rom_hash(), seed, *machine.quirks());
+        movie.instructions_per_frame = machine.instructions_per_frame();
+        movie
+    });
+
+    if let Some(path) = &options.trace_path {
+        match Tracer::create(path, TraceFilter::new()) {
+            Ok(tracer) => machine.set_tracer(Some(tracer)),
+            Err(err) => {
+                eprintln!("Error creating {}: {}", path, err);
+                process::exit(2);
+            }
+        }
+    }
+
+    if options.headless {
+        let frames = options.frames.unwrap_or_else(|| {
+            replay
+                .as_ref()
+                .map_or(cli::DEFAULT_FRAMES, |movie| movie.frames)
+        });
+        let script = replay.map_or_else(KeyScript::new, |movie| movie.keys);
+        let result = headless::run(
+            &mut machine,
+            RunLimit::Frames(frames),
+            &script,
+            &mut NullSink,
+        );
+
+        if let Some(movie) = &mut recording {
+            movie.frames = machine.frames();
+            movie.keys = script;
+            movie.keys.truncate(movie.frames);
+        }
+        finish(&mut machine, &options, recording);
+
+        print!("{}", headless::display_to_ascii(machine.ppu()));
+        if let Err(err) = result {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let sdl_context = or_exit(sdl2::init(), "SDL");
+    let video_subsystem = or_exit(sdl_context.video(), "video");
+    let mut audio = or_exit(SdlAudio::new(&sdl_context), "audio");
+
+    let window = video_subsystem
+        .window(
+            entry.title.as_deref().unwrap_or("Chip8 Emulator"),
+            SCREEN_WIDTH as u32 * options.scale,
+            SCREEN_HEIGHT as u32 * options.scale,
+        )
+        .position_centered()
+        .resizable()
+        .build();
+    let mut canvas = or_exit(or_exit(window, "window").into_canvas().build(), "canvas");
+    if options.fullscreen || config.fullscreen {
+        or_exit(video::toggle_fullscreen(&mut canvas), "fullscreen");
+    }
+    let texture_creator = canvas.texture_creator();
+    let mut screen = or_exit(
+        Screen::new(&texture_creator, options.scaling.unwrap_or(config.scaling)),
+        "display",
+    );
+
+    let mut event_pump = or_exit(sdl_context.event_pump(), "events");
+    let controller_subsystem = or_exit(sdl_context.game_controller(), "game controllers");
+    let mut input = Input::new(
+        keymap,
+        config.controller_map_for(&rom_name).clone(),
+        controller_subsystem,
+    );
+    let mut halted = false;
+    let mut rewind = RewindBuffer::default();
+    rewind.push(machine.save_state());
+    let mut scheduler = FrameScheduler::new(Instant::now());
+    let palettes = palette_cycle(palette);
+    let mut palette_index = 0;
+
+    while !input.escape_pressed && !machine.has_exited() {
+        input.poll(&mut event_pump, &mut machine);
+
+        if let Some(slot) = input.save_slot {
+            save_state(&machine, rom_path, slot);
+        }
+        if let Some(slot) = input.load_slot {
+            // A movie only stores its starting seed and key presses, so a
+            // state loaded partway through could not be replayed
+            if replay.is_some() || recording.is_some() {
+                println!("States cannot be loaded while recording or replaying.");
+            } else if load_state(&mut machine, rom_path, slot) {
+                halted = false;
+            }
+        }
+
+        if let Some(faster) = input.speed_step {
+            // A movie replays at the speed it was recorded with
+            if replay.is_some() || recording.is_some() {
+                println!("The speed cannot change while recording or replaying.");
+            } else {
+                let speed = step_speed(machine.instructions_per_frame(), faster);
+                machine.set_instructions_per_frame(speed);
+                println!("Speed: {} instructions per frame.", speed);
+            }
+        }
+        if input.toggle_fullscreen {
+            if let Err(err) = video::toggle_fullscreen(&mut canvas) {
+                eprintln!("Error switching fullscreen: {}", err);
+            }
+        }
+        if input.next_palette {
+            palette_index = (palette_index + 1) % palettes.len();
+            println!("Palette: {}.", palettes[palette_index].0);
+        }
+        let palette = palettes[palette_index].1;
+        scheduler.set_speed(if input.fast_forward {
+            FAST_FORWARD
+        } else if input.slow_motion {
+            SLOW_MOTION
+        } else {
+            1.0
+        });
+
+        for _ in 0..scheduler.frames_due(Instant::now()) {
+            // Holding Backspace plays the game backwards, one frame per frame.
+            // A faulting program halts but the window stays open on the last
+            // frame
+            if input.rewinding {
+                if let Some(state) = rewind.step_back() {
+                    match machine.load_state(state) {
+                        Ok(()) => halted = false,
+                        Err(err) => eprintln!("Error rewinding: {}", err),
+                    }
+                }
+            } else if !halted {
+                // A replay drives the keypad until it ends, then hands it back
+                if let Some(movie) = replay
+                    .as_ref()
+                    .filter(|movie| machine.frames() < movie.frames)
+                {
+                    machine.set_keys(movie.keys.keys_at(machine.frames()));
+                }
+                input.keypad_locked = replay
+                    .as_ref()
+                    .is_some_and(|movie| machine.frames() + 1 < movie.frames);
+                if let Some(movie) = &mut recording {
+                    movie.record(machine.frames(), machine.keyboard().pressed());
+                }
+
+                match machine.run_frame() {
+                    Ok(()) => rewind.push(machine.save_state()),
+                    Err(err) => {
+                        eprintln!("Error: {}", err);
+                        halted = true;
+                    }
+                }
+            }
+            if machine.has_exited() {
+                break;
+            }
+        }
+        if halted || input.rewinding {
+            audio.update(Sound::Silent);
+        } else {
+            audio.update(machine.sound());
+        }
+
+        let drawn = screen.draw(
+            &mut canvas,
+            machine.display(),
+            machine.resolution(),
+            &palette,
+        );
+        if let Err(err) = drawn {
+            // Keep the trace and recording made so far
+            eprintln!("Error drawing the display: {}", err);
+            finish(&mut machine, &options, recording);
+            process::exit(2);
+        }
+
+        // Sleep until the next frame is due, however long this one took
+        thread::sleep(
+            scheduler
+                .next_frame()
+                .saturating_duration_since(Instant::now()),
+        );
+    }
+
+    finish(&mut machine, &options, recording);
+}
+
+/// Flushes the trace and writes the recorded movie, if any.
+fn finish(machine: &mut Machine, options: &Options, recording: Option<Movie>) {
+    if let (Some(tracer), Some(path)) = (machine.take_tracer(), &options.trace_path) {
+        if let Err(err) = tracer.finish() {
+            eprintln!("Error writing {}: {}", path, err);
+            process::exit(2);
+        }
+    }
+
+    if let (Some(movie), Some(path)) = (recording, &options.record_path) {
+        if let Err(err) = fs::write(path, movie.to_string()) {
+            eprintln!("Error writing {}: {}", path, err);
+            process::exit(2);
+        }
+    }
+}
+
+/// The next entry of [`SPEED_STEPS`] above or below `speed`.
+fn step_speed(speed: usize, faster: bool) -> usize {
+    if faster {
+        SPEED_STEPS.into_iter().find(|step| *step > speed)
+    } else {
+        SPEED_STEPS.into_iter().rev().find(|step| *step < speed)
+    }
+    .unwrap_or(speed)
+}
+
+/// Exits with a message if setting up part of SDL failed.
+fn or_exit<T, E: Display>(result: Result<T, E>, what: &str) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("Error initializing {}: {}", what, err);
+        process::exit(2);
+    })
+}
+
+/// The palettes F9 cycles through: the starting one, then the built-in themes.
+fn palette_cycle(start: Palette) -> Vec<(&'static str, Palette)> {
+    let themes = Palette::THEME_NAMES.into_iter().zip(Palette::THEMES);
+    let mut palettes = match themes.clone().find(|(_, theme)| *theme == start) {
+        Some(theme) => vec![theme],
+        None => vec![("custom", start)],
+    };
+    palettes.extend(themes.filter(|(_, theme)| *theme != start));
+    palettes
+}
+
+/// Save states live next to the ROM, as `<rom>.state<slot>`.
+fn state_path(rom_path: &str, slot: u8) -> String {
+    format!("{}.state{}", rom_path, slot)
+}
+
+fn save_state(machine: &Machine, rom_path: &str, slot: u8) {
+    let path = state_path(rom_path, slot);
+    match fs::write(&path, machine.save_state()) {
+        Ok(()) => println!("Saved state to slot {}.", slot),
+        Err(err) => eprintln!("Error writing {}: {}", path, err),
+    }
+}
+
+/// Returns whether the state was loaded.
+fn load_state(machine: &mut Machine, rom_path: &str, slot: u8) -> bool {
+    let path = state_path(rom_path, slot);
+    let result = fs::read(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|state| machine.load_state(&state).map_err(|err| err.to_string()));
+
+    match result {
+        Ok(()) => {
+            println!("Loaded state from slot {}.", slot);
+            true
+        }
+        Err(err) => {
+            eprintln!("Error loading {}: {}", path, err);
+            false
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! The `disasm` and `asm` subcommands, which work on files instead of
+//! running a ROM. Shared by `chip8` and `chip8-headless`, and run before any
+//! SDL setup.
+
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use chip8::{assembler, disassembler, Rom};
+
+/// `<program> disasm <rom>`: prints a listing of the ROM.
+pub fn disasm(program: &str, args: &[String]) {
+    let [path] = args else {
+        eprintln!("Usage: {} disasm <rom>", program);
+        process::exit(2);
+    };
+
+    match Rom::build(path) {
+        Ok(rom) => print!("{}", disassembler::disassemble(&rom)),
+        Err(err) => {
+            eprintln!("Error reading {}: {}", path, err);
+            process::exit(2);
+        }
+    }
+}
+
+/// `<program> asm <source> [output]`: assembles the source into a ROM, by
+/// default next to it with a `.ch8` extension.
+pub fn asm(program: &str, args: &[String]) {
+    let (source, output) = match args {
+        [source] => (source, Path::new(source).with_extension("ch8")),
+        [source, output] => (source, output.into()),
+        _ => {
+            eprintln!("Usage: {} asm <source> [output]", program);
+            process::exit(2);
+        }
+    };
+
+    let rom = assembler::assemble_file(Path::new(source)).unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    });
+    if let Err(err) = fs::write(&output, rom) {
+        eprintln!("Error writing {}: {}", output.display(), err);
+        process::exit(2);
+    }
+}
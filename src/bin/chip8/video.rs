@@ -0,0 +1,76 @@
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
+use sdl2::video::{FullscreenType, WindowContext};
+
+use chip8::palette::Palette;
+use chip8::viewport::{Scaling, Viewport};
+use chip8::{HIRES_HEIGHT, HIRES_WIDTH};
+
+/// Draws the display by uploading it to a streaming texture each frame and
+/// scaling that into the window.
+pub struct Screen<'a> {
+    /// Big enough for the hi-res display; low-res frames use the top left.
+    texture: Texture<'a>,
+    scaling: Scaling,
+}
+
+impl<'a> Screen<'a> {
+    pub fn new(
+        texture_creator: &'a TextureCreator<WindowContext>,
+        scaling: Scaling,
+    ) -> Result<Self, String> {
+        let texture = texture_creator
+            .create_texture_streaming(
+                PixelFormatEnum::RGB24,
+                HIRES_WIDTH as u32,
+                HIRES_HEIGHT as u32,
+            )
+            .map_err(|err| err.to_string())?;
+        Ok(Self { texture, scaling })
+    }
+
+    /// Draws the top left `resolution` of `display` and presents the frame.
+    pub fn draw(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        display: &[[u8; HIRES_WIDTH]; HIRES_HEIGHT],
+        resolution: (usize, usize),
+        palette: &Palette,
+    ) -> Result<(), String> {
+        let (width, height) = resolution;
+        self.texture.with_lock(None, |pixels, pitch| {
+            for (row, line) in display.iter().take(height).enumerate() {
+                let line_pixels = &mut pixels[row * pitch..row * pitch + width * 3];
+                for (rgb, &pixel) in line_pixels.chunks_exact_mut(3).zip(line) {
+                    let color = palette.color(pixel);
+                    rgb.copy_from_slice(&[color.r, color.g, color.b]);
+                }
+            }
+        })?;
+
+        let viewport = Viewport::fit(
+            (width as u32, height as u32),
+            canvas.output_size()?,
+            self.scaling,
+        );
+        canvas.set_draw_color(Color::BLACK);
+        canvas.clear();
+        canvas.copy(
+            &self.texture,
+            Rect::new(0, 0, width as u32, height as u32),
+            Rect::new(viewport.x, viewport.y, viewport.width, viewport.height),
+        )?;
+        canvas.present();
+        Ok(())
+    }
+}
+
+/// Switches between a window and fullscreen at the desktop resolution.
+pub fn toggle_fullscreen(canvas: &mut WindowCanvas) -> Result<(), String> {
+    let window = canvas.window_mut();
+    match window.fullscreen_state() {
+        FullscreenType::Off => window.set_fullscreen(FullscreenType::Desktop),
+        _ => window.set_fullscreen(FullscreenType::Off),
+    }
+}
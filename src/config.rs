@@ -0,0 +1,277 @@
+//! User configuration, read from a TOML file.
+//!
+//! ```toml
+//! [keyboard]
+//! layout = "cosmac"             # or "hex"
+//! keys = { Space = "5" }        # extra bindings on top of the layout
+//!
+//! [controller]
+//! buttons = { x = "0", start = "F" } # on top of D-pad 2/4/6/8, A/B 5
+//!
+//! [display]
+//! palette = "amber"             # a theme or 2 or 4 colours, see `--palette`
+//! scaling = "integer"           # or "aspect" (the default) or "stretch"
+//! fullscreen = true
+//!
+//! # Overrides for a single ROM, keyed by its file name
+//! [roms.PONG.keyboard]
+//! layout = "hex"
+//!
+//! [roms.PONG.controller]
+//! buttons = { dpup = "1", dpdown = "4" }
+//! ```
+//!
+//! Every section is optional; missing settings keep their defaults.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use toml::{Table, Value};
+
+use crate::keymap::KeyMap;
+use crate::palette::Palette;
+use crate::romdb::RomEntry;
+use crate::viewport::Scaling;
+
+/// File the SDL frontend reads from the working directory, if present.
+pub const CONFIG_FILE: &str = "chip8.toml";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub keyboard: KeyMap,
+    /// Game controller buttons, by SDL button name.
+    pub controller: KeyMap,
+    /// Display colours, unless the command line or ROM database set them.
+    pub palette: Option<Palette>,
+    pub scaling: Scaling,
+    /// Start in fullscreen.
+    pub fullscreen: bool,
+    /// Per-ROM overrides by ROM file name.
+    pub roms: HashMap<String, RomConfig>,
+}
+
+/// Settings that replace the global ones for one ROM.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RomConfig {
+    pub keyboard: Option<KeyMap>,
+    pub controller: Option<KeyMap>,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Self {
+            keyboard: KeyMap::default(),
+            controller: KeyMap::gamepad(),
+            palette: None,
+            scaling: Scaling::default(),
+            fullscreen: false,
+            roms: HashMap::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::parse(&source)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let table: Table = source
+            .parse()
+            .map_err(|err: toml::de::Error| err.message().to_string())?;
+        let mut config = Self::new();
+
+        check_settings(&table, &["keyboard", "controller", "display", "roms"], None)?;
+        if let Some(keyboard) = table.get("keyboard") {
+            config.keyboard = parse_keyboard(keyboard, &config.keyboard, "keyboard")?;
+        }
+        if let Some(controller) = table.get("controller") {
+            config.controller = parse_controller(controller, &config.controller, "controller")?;
+        }
+        if let Some(display) = table.get("display") {
+            config.parse_display(display)?;
+        }
+        if let Some(roms) = table.get("roms") {
+            for (rom, value) in as_table(roms, "roms")? {
+                let rom_config = config.parse_rom(value, &format!("roms.{}", rom))?;
+                config.roms.insert(rom.clone(), rom_config);
+            }
+        }
+
+        Ok(config)
+    }
+
+    fn parse_display(&mut self, value: &Value) -> Result<(), String> {
+        let table = as_table(value, "display")?;
+        check_settings(
+            table,
+            &["palette", "scaling", "fullscreen"],
+            Some("display"),
+        )?;
+        let string = |name: &str| match table.get(name) {
+            Some(value) => value
+                .as_str()
+                .map(Some)
+                .ok_or_else(|| format!("`display.{}` must be a string", name)),
+            None => Ok(None),
+        };
+
+        if let Some(palette) = string("palette")? {
+            self.palette =
+                Some(Palette::parse(palette).map_err(|err| format!("display.palette: {}", err))?);
+        }
+        if let Some(scaling) = string("scaling")? {
+            self.scaling = Scaling::from_name(scaling).ok_or_else(|| {
+                format!(
+                    "unknown scaling `{}`, expected one of {}",
+                    scaling,
+                    Scaling::NAMES.join(", ")
+                )
+            })?;
+        }
+        if let Some(fullscreen) = table.get("fullscreen") {
+            self.fullscreen = fullscreen
+                .as_bool()
+                .ok_or("`display.fullscreen` must be true or false")?;
+        }
+
+        Ok(())
+    }
+
+    /// Parses a `[roms.<name>]` table. Called after the global settings so
+    /// overrides can build on them.
+    fn parse_rom(&self, value: &Value, path: &str) -> Result<RomConfig, String> {
+        let table = as_table(value, path)?;
+        check_settings(table, &["keyboard", "controller"], Some(path))?;
+
+        let setting = |name| (table.get(name), format!("{}.{}", path, name));
+        let keyboard = match setting("keyboard") {
+            (Some(value), path) => Some(parse_keyboard(value, &self.keyboard, &path)?),
+            (None, _) => None,
+        };
+        let controller = match setting("controller") {
+            (Some(value), path) => Some(parse_controller(value, &self.controller, &path)?),
+            (None, _) => None,
+        };
+
+        Ok(RomConfig {
+            keyboard,
+            controller,
+        })
+    }
+
+    /// The key map for the ROM with file name `rom`.
+    pub fn keymap_for(&self, rom: &str) -> &KeyMap {
+        self.rom_keymap(rom).unwrap_or(&self.keyboard)
+    }
+
+    /// The key map for the ROM with file name `rom` and ROM database entry
+    /// `entry`. A `[roms.<rom>.keyboard]` section wins over the database,
+    /// whose bindings otherwise go on top of the global map.
+    pub fn keymap_for_entry(&self, rom: &str, entry: &RomEntry) -> KeyMap {
+        match self.rom_keymap(rom) {
+            Some(keymap) => keymap.clone(),
+            None => entry.keymap(&self.keyboard),
+        }
+    }
+
+    fn rom_keymap(&self, rom: &str) -> Option<&KeyMap> {
+        self.roms.get(rom).and_then(|rom| rom.keyboard.as_ref())
+    }
+
+    /// The controller button map for the ROM with file name `rom`.
+    pub fn controller_map_for(&self, rom: &str) -> &KeyMap {
+        self.roms
+            .get(rom)
+            .and_then(|rom| rom.controller.as_ref())
+            .unwrap_or(&self.controller)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses a keyboard table. `layout` replaces `base` with a preset, then
+/// `keys` adds bindings on top.
+fn parse_keyboard(value: &Value, base: &KeyMap, path: &str) -> Result<KeyMap, String> {
+    let table = as_table(value, path)?;
+    check_settings(table, &["layout", "keys"], Some(path))?;
+    let mut keymap = base.clone();
+
+    if let Some(layout) = table.get("layout") {
+        let layout = layout
+            .as_str()
+            .ok_or_else(|| format!("`{}.layout` must be a string", path))?;
+        keymap = KeyMap::from_name(layout).ok_or_else(|| {
+            format!(
+                "unknown layout `{}`, expected one of {}",
+                layout,
+                KeyMap::LAYOUT_NAMES.join(", ")
+            )
+        })?;
+    }
+
+    if let Some(keys) = table.get("keys") {
+        bind_all(&mut keymap, keys, &format!("{}.keys", path))?;
+    }
+
+    Ok(keymap)
+}
+
+/// Parses a controller table, whose `buttons` add bindings on top of `base`.
+fn parse_controller(value: &Value, base: &KeyMap, path: &str) -> Result<KeyMap, String> {
+    let table = as_table(value, path)?;
+    check_settings(table, &["buttons"], Some(path))?;
+    let mut keymap = base.clone();
+
+    if let Some(buttons) = table.get("buttons") {
+        bind_all(&mut keymap, buttons, &format!("{}.buttons", path))?;
+    }
+
+    Ok(keymap)
+}
+
+/// Adds each `name = key` pair of the table `value` to `keymap`.
+pub(crate) fn bind_all(keymap: &mut KeyMap, value: &Value, path: &str) -> Result<(), String> {
+    for (name, key) in as_table(value, path)? {
+        let key = parse_hex_key(key)
+            .ok_or_else(|| format!("`{}.{}` must be a hex key 0-F", path, name))?;
+        keymap.bind(name, key);
+    }
+
+    Ok(())
+}
+
+/// A hex key given as a string (`"A"`) or an integer (`10`).
+fn parse_hex_key(value: &Value) -> Option<u8> {
+    let key = match value {
+        Value::String(digit) => u8::from_str_radix(digit, 16).ok()?,
+        Value::Integer(key) => u8::try_from(*key).ok()?,
+        _ => return None,
+    };
+    (key <= 0xF).then_some(key)
+}
+
+/// Rejects keys of `table` not in `known`, which are most likely typos.
+pub(crate) fn check_settings(
+    table: &Table,
+    known: &[&str],
+    path: Option<&str>,
+) -> Result<(), String> {
+    match table.keys().find(|name| !known.contains(&name.as_str())) {
+        Some(name) => match path {
+            Some(path) => Err(format!("unknown setting `{}.{}`", path, name)),
+            None => Err(format!("unknown setting `{}`", name)),
+        },
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn as_table<'a>(value: &'a Value, path: &str) -> Result<&'a Table, String> {
+    value
+        .as_table()
+        .ok_or_else(|| format!("`{}` must be a table", path))
+}
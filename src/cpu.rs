@@ -1,9 +1,13 @@
+use crate::error::ExecError;
 use crate::instruction::Instruction;
 use crate::keyboard::Keyboard;
 use crate::ppu::Ppu;
 use crate::program_counter::ProgramCounter;
+use crate::quirks::Quirks;
 use crate::ram::Ram;
 use crate::registers::Registers;
+use crate::rng::Rng;
+use crate::state::{StateError, StateReader, StateWriter};
 use crate::timer::Timer;
 
 const MASK_MSB: u16 = 0xF000;
@@ -13,9 +17,15 @@ const MASK_N: u16 = 0x000F;
 const MASK_X: u16 = 0x0F00;
 const MASK_Y: u16 = 0x00F0;
 
+/// Register file and program counter; executes one instruction at a time.
 pub struct Cpu {
     pub registers: Registers,
     pub program_counter: ProgramCounter,
+    /// Set by `DXYN` under the display wait quirk; execution stalls until
+    /// the next frame clears it.
+    pub vblank_wait: bool,
+    /// Set by `00FD`; execution stops for good.
+    pub exited: bool,
 }
 
 impl Cpu {
@@ -23,6 +33,8 @@ impl Cpu {
         Self {
             registers: Registers::new(),
             program_counter: ProgramCounter::new(),
+            vblank_wait: false,
+            exited: false,
         }
     }
 
@@ -32,11 +44,15 @@ impl Cpu {
         ppu: &mut Ppu,
         timer: &mut Timer,
         keyboard: &mut Keyboard,
-        shift_quirk: bool,
-        jump_quirk: bool,
-    ) {
+        rng: &mut Rng,
+        quirks: &Quirks,
+    ) -> Result<(), ExecError> {
+        if self.vblank_wait || self.exited {
+            return Ok(());
+        }
+
         // All instructions are 2 bytes long and are stored most-significant-byte first.
-        let instruction = ram.get_instruction(self.program_counter.get_value());
+        let instruction = ram.get_instruction(self.program_counter.get_value())?;
 
         // msb  - the upper 4 bits of the instruction
         // addr - the lowest 12 bits of the instruction
@@ -46,33 +62,47 @@ impl Cpu {
         // y    - the upper 4 bits of the low byte of the instruction
         let (msb, addr, nn, n, x, y) = self.mask_opcodes(instruction);
 
-        println!("Instruction: {:#X}", instruction);
-        println!(
-            "msb: {:#X}, addr: {:#X}, nn: {:#X}, n: {:#X}, x: {:#X}, y: {:#X}",
-            msb, addr, nn, n, x, y
-        );
-
         match msb {
-            0x0 => Instruction::exec_0x0(self, ppu, nn),
+            0x0 => Instruction::exec_0x0(self, ppu, addr),
             0x1 => Instruction::exec_0x1(self, addr),
             0x2 => Instruction::exec_0x2(self, addr),
-            0x3 => Instruction::exec_0x3(self, nn, x),
-            0x4 => Instruction::exec_0x4(self, nn, x),
-            0x5 => Instruction::exec_0x5(self, x, y),
+            0x3 => Instruction::exec_0x3(self, ram, nn, x),
+            0x4 => Instruction::exec_0x4(self, ram, nn, x),
+            0x5 => Instruction::exec_0x5(self, ram, n, x, y),
             0x6 => Instruction::exec_0x6(self, nn, x),
             0x7 => Instruction::exec_0x7(self, nn, x),
-            0x8 => Instruction::exec_0x8(self, n, x, y, shift_quirk),
-            0x9 => Instruction::exec_0x9(self, x, y),
+            0x8 => Instruction::exec_0x8(self, n, x, y, quirks),
+            0x9 => Instruction::exec_0x9(self, ram, x, y),
             0xA => Instruction::exec_0xa(self, addr),
-            0xB => Instruction::exec_0xb(self, addr, x, jump_quirk),
-            0xC => Instruction::exec_0xc(self, nn, x),
-            0xD => Instruction::exec_0xd(self, ram, ppu, n, x, y),
-            0xE => Instruction::exec_0xe(self, nn, x, keyboard),
-            0xF => Instruction::exec_0xf(self, ram, timer, keyboard, nn, x),
-            _ => panic!("Invalid instruction."),
+            0xB => Instruction::exec_0xb(self, addr, x, quirks),
+            0xC => Instruction::exec_0xc(self, rng, nn, x),
+            0xD => Instruction::exec_0xd(self, ram, ppu, n, x, y, quirks),
+            0xE => Instruction::exec_0xe(self, ram, nn, x, keyboard),
+            0xF => Instruction::exec_0xf(self, ram, ppu, timer, keyboard, nn, x, quirks),
+            _ => unreachable!("msb is a 4-bit value"),
         }
     }
 
+    pub(crate) fn save(&self, writer: &mut StateWriter) {
+        self.registers.save(writer);
+        writer.u16(self.program_counter.get_value());
+        writer.bool(self.vblank_wait);
+        writer.bool(self.exited);
+    }
+
+    pub(crate) fn load(reader: &mut StateReader) -> Result<Self, StateError> {
+        let registers = Registers::load(reader)?;
+        let mut program_counter = ProgramCounter::new();
+        program_counter.set_value(reader.u16()?);
+
+        Ok(Self {
+            registers,
+            program_counter,
+            vblank_wait: reader.bool()?,
+            exited: reader.bool()?,
+        })
+    }
+
     fn mask_opcodes(&self, instruction: u16) -> (u8, u16, u8, u8, u8, u8) {
         (
             ((instruction & MASK_MSB) >> 12) as u8,
@@ -84,3 +114,9 @@ impl Cpu {
         )
     }
 }
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
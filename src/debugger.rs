@@ -0,0 +1,167 @@
+//! Breakpoints, watchpoints and single-stepping on top of [`Machine`].
+
+use crate::error::ExecError;
+use crate::machine::Machine;
+
+/// Stops execution before an instruction runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// The program counter reaches the address.
+    Address(u16),
+    /// The opcode at the program counter matches `value` in the bits set in
+    /// `mask`.
+    Opcode { value: u16, mask: u16 },
+}
+
+impl Breakpoint {
+    /// Parses an opcode pattern of four hex digits where `?` matches any
+    /// digit, e.g. `D??F` or `8??6`.
+    pub fn opcode_pattern(pattern: &str) -> Option<Self> {
+        if pattern.len() != 4 {
+            return None;
+        }
+
+        let mut value = 0;
+        let mut mask = 0;
+        for digit in pattern.chars() {
+            value <<= 4;
+            mask <<= 4;
+            if digit != '?' {
+                value |= digit.to_digit(16)? as u16;
+                mask |= 0xF;
+            }
+        }
+
+        Some(Self::Opcode { value, mask })
+    }
+
+    fn matches(&self, pc: u16, opcode: u16) -> bool {
+        match *self {
+            Self::Address(address) => pc == address,
+            Self::Opcode { value, mask } => opcode & mask == value,
+        }
+    }
+}
+
+/// Stops execution after an instruction changes a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watchpoint {
+    Memory(u16),
+    Register(u8),
+}
+
+impl Watchpoint {
+    fn read(&self, machine: &Machine) -> u8 {
+        match *self {
+            Self::Memory(address) => machine.ram().read_byte(address).unwrap_or(0),
+            Self::Register(index) => machine.cpu().registers.get_vn(index),
+        }
+    }
+}
+
+/// Why execution stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The requested number of instructions ran.
+    Done,
+    /// The breakpoint with this index was hit.
+    Breakpoint(usize),
+    /// The watchpoint with this index changed from `old` to `new`.
+    Watchpoint { index: usize, old: u8, new: u8 },
+    /// An instruction failed.
+    Error(ExecError),
+    /// The program ended with `00FD`.
+    Exited,
+}
+
+/// Drives a [`Machine`] one instruction at a time, ticking its timers every
+/// [`Machine::instructions_per_frame`] instructions so timing matches a
+/// normal run.
+#[derive(Debug, Default)]
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    cycles: u64,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of instructions executed so far.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn remove_breakpoint(&mut self, index: usize) -> Option<Breakpoint> {
+        (index < self.breakpoints.len()).then(|| self.breakpoints.remove(index))
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> usize {
+        self.watchpoints.push(watchpoint);
+        self.watchpoints.len() - 1
+    }
+
+    pub fn remove_watchpoint(&mut self, index: usize) -> Option<Watchpoint> {
+        (index < self.watchpoints.len()).then(|| self.watchpoints.remove(index))
+    }
+
+    /// Runs up to `count` instructions, stopping early at a breakpoint, a
+    /// watchpoint, an error or program exit. Breakpoints are not checked
+    /// before the first instruction, so continuing from a breakpoint moves on.
+    pub fn run(&mut self, machine: &mut Machine, count: u64) -> StopReason {
+        for executed in 0..count {
+            if machine.has_exited() {
+                return StopReason::Exited;
+            }
+
+            if executed > 0 {
+                if let Some(index) = self.breakpoint_hit(machine) {
+                    return StopReason::Breakpoint(index);
+                }
+            }
+
+            let before: Vec<u8> = self.watchpoints.iter().map(|w| w.read(machine)).collect();
+
+            if let Err(err) = machine.run_instruction() {
+                return StopReason::Error(err);
+            }
+            self.cycles += 1;
+            if self.cycles % machine.instructions_per_frame() as u64 == 0 {
+                machine.tick_timers();
+            }
+
+            for (index, watchpoint) in self.watchpoints.iter().enumerate() {
+                let (old, new) = (before[index], watchpoint.read(machine));
+                if old != new {
+                    return StopReason::Watchpoint { index, old, new };
+                }
+            }
+        }
+
+        StopReason::Done
+    }
+
+    fn breakpoint_hit(&self, machine: &Machine) -> Option<usize> {
+        let pc = machine.cpu().program_counter.get_value();
+        let opcode = machine.ram().get_instruction(pc).ok()?;
+
+        self.breakpoints
+            .iter()
+            .position(|breakpoint| breakpoint.matches(pc, opcode))
+    }
+}
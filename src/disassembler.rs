@@ -0,0 +1,284 @@
+//! Turns machine code into Cowgod-style mnemonics, extended with the
+//! SUPER-CHIP and XO-CHIP instructions.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::machine::ROM_START_ADDRESS;
+use crate::rom::Rom;
+
+/// Number of bytes per `db` line in a listing.
+const BYTES_PER_LINE: usize = 8;
+
+/// Decodes the instruction at the start of `bytes`, returning its mnemonic
+/// and length in bytes (4 for `F000 NNNN`, otherwise 2). Returns `None` if
+/// the bytes are not a valid instruction.
+pub fn decode(bytes: &[u8]) -> Option<(String, usize)> {
+    decode_with(bytes, &|address| format!("{:#05X}", address))
+}
+
+/// Disassembles a ROM loaded at [`ROM_START_ADDRESS`] into a listing that
+/// the [assembler](crate::assembler) turns back into the same bytes.
+///
+/// Code is found by following execution from the start address through
+/// jumps, calls and skips; everything else is emitted as `db` data. Call
+/// targets are labelled `sub_NNN`, jump targets `label_NNN` and addresses
+/// loaded into I `data_NNN`.
+pub fn disassemble(rom: &Rom) -> String {
+    let analysis = Analysis::run(&rom.data);
+    let data = &rom.data;
+    let mut output = format!("; {} bytes\n", data.len());
+
+    let mut offset = 0;
+    while offset < data.len() {
+        let address = address_of(offset);
+        if let Some(kind) = analysis.labels.get(&address) {
+            let _ = writeln!(output, "\n{}:", kind.name(address));
+        }
+
+        let (text, length) = match analysis.instructions.get(&offset) {
+            Some(&length) => {
+                let resolve = |target| analysis.operand(target);
+                let (text, _) =
+                    decode_with(&data[offset..], &resolve).expect("analysed instructions decode");
+                (text, length)
+            }
+            None => {
+                let mut length = 1;
+                while length < BYTES_PER_LINE
+                    && offset + length < data.len()
+                    && !analysis.instructions.contains_key(&(offset + length))
+                    && !analysis.labels.contains_key(&address_of(offset + length))
+                {
+                    length += 1;
+                }
+                let bytes: Vec<String> = data[offset..offset + length]
+                    .iter()
+                    .map(|byte| format!("{:#04X}", byte))
+                    .collect();
+                (format!("db {}", bytes.join(", ")), length)
+            }
+        };
+
+        if analysis.instructions.contains_key(&offset) {
+            let hex: String = data[offset..offset + length]
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect();
+            let _ = writeln!(output, "    {:<24}; {:03X}: {}", text, address, hex);
+        } else {
+            let _ = writeln!(output, "    {:<24}; {:03X}", text, address);
+        }
+        offset += length;
+    }
+
+    output
+}
+
+/// What an address is used as, in order of precedence when naming it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LabelKind {
+    Data,
+    Jump,
+    Subroutine,
+}
+
+impl LabelKind {
+    fn name(self, address: u16) -> String {
+        match self {
+            Self::Data => format!("data_{:03X}", address),
+            Self::Jump => format!("label_{:03X}", address),
+            Self::Subroutine => format!("sub_{:03X}", address),
+        }
+    }
+}
+
+/// Which bytes of a ROM are reachable code, and which addresses deserve a
+/// label.
+struct Analysis {
+    /// Length of the instruction starting at each code offset.
+    instructions: BTreeMap<usize, usize>,
+    labels: BTreeMap<u16, LabelKind>,
+}
+
+impl Analysis {
+    fn run(data: &[u8]) -> Self {
+        let mut covered = vec![false; data.len()];
+        let mut instructions = BTreeMap::new();
+        let mut targets: BTreeMap<u16, LabelKind> = BTreeMap::new();
+        let mut pending = vec![ROM_START_ADDRESS];
+
+        while let Some(start) = pending.pop() {
+            let mut address = start;
+
+            while let Some(offset) = offset_of(address, data.len()) {
+                if covered[offset] {
+                    break;
+                }
+                let Some((_, length)) = decode(&data[offset..]) else {
+                    break;
+                };
+                if covered[offset..offset + length].iter().any(|&byte| byte) {
+                    break;
+                }
+
+                covered[offset..offset + length].fill(true);
+                instructions.insert(offset, length);
+
+                let opcode = u16::from_be_bytes([data[offset], data[offset + 1]]);
+                let nnn = opcode & 0x0FFF;
+                let next = address.wrapping_add(length as u16);
+
+                match opcode >> 12 {
+                    0x0 if opcode == 0x00EE || opcode == 0x00FD => break,
+                    0x1 | 0xB => {
+                        add_target(&mut targets, nnn, LabelKind::Jump);
+                        pending.push(nnn);
+                        break;
+                    }
+                    0x2 => {
+                        add_target(&mut targets, nnn, LabelKind::Subroutine);
+                        pending.push(nnn);
+                    }
+                    0xA => add_target(&mut targets, nnn, LabelKind::Data),
+                    0xF if opcode == 0xF000 => {
+                        let long = u16::from_be_bytes([data[offset + 2], data[offset + 3]]);
+                        add_target(&mut targets, long, LabelKind::Data);
+                    }
+                    0x3 | 0x4 | 0x5 | 0x9 | 0xE if is_skip(opcode) => {
+                        let skipped = match offset_of(next, data.len()) {
+                            Some(next_offset) if data[next_offset..].starts_with(&[0xF0, 0x00]) => {
+                                4
+                            }
+                            _ => 2,
+                        };
+                        pending.push(next.wrapping_add(skipped));
+                    }
+                    _ => {}
+                }
+
+                address = next;
+            }
+        }
+
+        // A label can only be placed at the start of an instruction or on a
+        // data byte, not in the middle of an instruction
+        let labels = targets
+            .into_iter()
+            .filter(|(address, _)| match offset_of(*address, data.len()) {
+                Some(offset) => instructions.contains_key(&offset) || !covered[offset],
+                None => false,
+            })
+            .collect();
+
+        Self {
+            instructions,
+            labels,
+        }
+    }
+
+    fn operand(&self, address: u16) -> String {
+        match self.labels.get(&address) {
+            Some(kind) => kind.name(address),
+            None => format!("{:#05X}", address),
+        }
+    }
+}
+
+fn add_target(targets: &mut BTreeMap<u16, LabelKind>, address: u16, kind: LabelKind) {
+    let entry = targets.entry(address).or_insert(kind);
+    *entry = (*entry).max(kind);
+}
+
+fn is_skip(opcode: u16) -> bool {
+    match opcode >> 12 {
+        0x3 | 0x4 => true,
+        0x5 | 0x9 => opcode & 0x000F == 0,
+        0xE => matches!(opcode & 0x00FF, 0x9E | 0xA1),
+        _ => false,
+    }
+}
+
+fn address_of(offset: usize) -> u16 {
+    (ROM_START_ADDRESS as usize + offset) as u16
+}
+
+fn offset_of(address: u16, len: usize) -> Option<usize> {
+    let offset = address.checked_sub(ROM_START_ADDRESS)? as usize;
+    (offset < len).then_some(offset)
+}
+
+/// Decodes like [`decode`], formatting address operands with `address`.
+fn decode_with(bytes: &[u8], address: &dyn Fn(u16) -> String) -> Option<(String, usize)> {
+    let opcode = u16::from_be_bytes([*bytes.first()?, *bytes.get(1)?]);
+
+    if opcode == 0xF000 {
+        let long = u16::from_be_bytes([*bytes.get(2)?, *bytes.get(3)?]);
+        return Some((format!("LD I, LONG {}", address(long)), 4));
+    }
+
+    mnemonic(opcode, address).map(|text| (text, 2))
+}
+
+fn mnemonic(opcode: u16, address: &dyn Fn(u16) -> String) -> Option<String> {
+    let addr = opcode & 0x0FFF;
+    let nn = opcode & 0x00FF;
+    let n = opcode & 0x000F;
+    let x = (opcode & 0x0F00) >> 8;
+    let y = (opcode & 0x00F0) >> 4;
+
+    let text = match (opcode >> 12, x, y, n) {
+        (0x0, 0x0, 0xE, 0x0) => "CLS".to_string(),
+        (0x0, 0x0, 0xE, 0xE) => "RET".to_string(),
+        (0x0, 0x0, 0xC, _) => format!("SCD {}", n),
+        (0x0, 0x0, 0xD, _) => format!("SCU {}", n),
+        (0x0, 0x0, 0xF, 0xB) => "SCR".to_string(),
+        (0x0, 0x0, 0xF, 0xC) => "SCL".to_string(),
+        (0x0, 0x0, 0xF, 0xD) => "EXIT".to_string(),
+        (0x0, 0x0, 0xF, 0xE) => "LOW".to_string(),
+        (0x0, 0x0, 0xF, 0xF) => "HIGH".to_string(),
+        (0x1, ..) => format!("JP {}", address(addr)),
+        (0x2, ..) => format!("CALL {}", address(addr)),
+        (0x3, ..) => format!("SE V{:X}, {:#04X}", x, nn),
+        (0x4, ..) => format!("SNE V{:X}, {:#04X}", x, nn),
+        (0x5, _, _, 0x0) => format!("SE V{:X}, V{:X}", x, y),
+        (0x5, _, _, 0x2) => format!("SAVE V{:X}, V{:X}", x, y),
+        (0x5, _, _, 0x3) => format!("LOAD V{:X}, V{:X}", x, y),
+        (0x6, ..) => format!("LD V{:X}, {:#04X}", x, nn),
+        (0x7, ..) => format!("ADD V{:X}, {:#04X}", x, nn),
+        (0x8, _, _, 0x0) => format!("LD V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x1) => format!("OR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x2) => format!("AND V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x3) => format!("XOR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x4) => format!("ADD V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x5) => format!("SUB V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x6) => format!("SHR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x7) => format!("SUBN V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0xE) => format!("SHL V{:X}, V{:X}", x, y),
+        (0x9, _, _, 0x0) => format!("SNE V{:X}, V{:X}", x, y),
+        (0xA, ..) => format!("LD I, {}", address(addr)),
+        (0xB, ..) => format!("JP V0, {}", address(addr)),
+        (0xC, ..) => format!("RND V{:X}, {:#04X}", x, nn),
+        (0xD, ..) => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        (0xE, _, 0x9, 0xE) => format!("SKP V{:X}", x),
+        (0xE, _, 0xA, 0x1) => format!("SKNP V{:X}", x),
+        (0xF, _, 0x0, 0x1) => format!("PLANE {}", x),
+        (0xF, 0x0, 0x0, 0x2) => "AUDIO".to_string(),
+        (0xF, _, 0x3, 0xA) => format!("PITCH V{:X}", x),
+        (0xF, _, 0x0, 0x7) => format!("LD V{:X}, DT", x),
+        (0xF, _, 0x0, 0xA) => format!("LD V{:X}, K", x),
+        (0xF, _, 0x1, 0x5) => format!("LD DT, V{:X}", x),
+        (0xF, _, 0x1, 0x8) => format!("LD ST, V{:X}", x),
+        (0xF, _, 0x1, 0xE) => format!("ADD I, V{:X}", x),
+        (0xF, _, 0x2, 0x9) => format!("LD F, V{:X}", x),
+        (0xF, _, 0x3, 0x0) => format!("LD HF, V{:X}", x),
+        (0xF, _, 0x3, 0x3) => format!("LD B, V{:X}", x),
+        (0xF, _, 0x5, 0x5) => format!("LD [I], V{:X}", x),
+        (0xF, _, 0x6, 0x5) => format!("LD V{:X}, [I]", x),
+        (0xF, _, 0x7, 0x5) => format!("LD R, V{:X}", x),
+        (0xF, _, 0x8, 0x5) => format!("LD V{:X}, R", x),
+        _ => return None,
+    };
+
+    Some(text)
+}
@@ -0,0 +1,39 @@
+use std::error::Error;
+use std::fmt;
+
+/// An error raised while executing a program.
+///
+/// When an instruction fails, the program counter is left pointing at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecError {
+    /// The opcode at `address` does not decode to a known instruction.
+    UnknownOpcode { address: u16, opcode: u16 },
+    /// `00EE` was executed with an empty call stack.
+    StackUnderflow,
+    /// `2NNN` was executed with a full call stack.
+    StackOverflow,
+    /// An instruction read or wrote memory outside of the address space.
+    MemoryOutOfBounds { address: u16 },
+    /// The program counter left the address space.
+    PcOutOfBounds { address: u16 },
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownOpcode { address, opcode } => {
+                write!(f, "unknown opcode {:#06X} at {:#05X}", opcode, address)
+            }
+            Self::StackUnderflow => write!(f, "return with an empty call stack"),
+            Self::StackOverflow => write!(f, "call stack overflow"),
+            Self::MemoryOutOfBounds { address } => {
+                write!(f, "memory access out of bounds at {:#X}", address)
+            }
+            Self::PcOutOfBounds { address } => {
+                write!(f, "program counter out of bounds at {:#X}", address)
+            }
+        }
+    }
+}
+
+impl Error for ExecError {}
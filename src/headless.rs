@@ -0,0 +1,220 @@
+//! Running programs without a window, for batch jobs and tests.
+
+use std::fmt::Write;
+
+use crate::audio::AudioSink;
+use crate::error::ExecError;
+use crate::machine::Machine;
+use crate::ppu::Ppu;
+
+/// How long a headless run lasts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunLimit {
+    Frames(u64),
+    Cycles(u64),
+}
+
+/// Scripted key input for a headless run.
+///
+/// Each entry sets the held keys, bit `n` for key `n`, at the start of a
+/// frame.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KeyScript {
+    events: Vec<(u64, u16)>,
+}
+
+impl KeyScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses one `<frame> <keys>` pair per line, where `<keys>` is the hex
+    /// digits of every key held from then on (`5`, `46`) or `-` to release
+    /// all keys. Blank lines and lines starting with `#` are skipped.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut script = Self::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (frame, keys) =
+                parse_event(line).map_err(|err| format!("line {}: {}", index + 1, err))?;
+            script.push(frame, keys);
+        }
+
+        Ok(script)
+    }
+
+    /// The `(frame, keys)` events in frame order.
+    pub fn events(&self) -> &[(u64, u16)] {
+        &self.events
+    }
+
+    /// The keys held during `frame`, i.e. set by the last event at or before
+    /// it.
+    pub fn keys_at(&self, frame: u64) -> u16 {
+        let position = self.events.partition_point(|(at, _)| *at <= frame);
+        position
+            .checked_sub(1)
+            .map_or(0, |index| self.events[index].1)
+    }
+
+    /// Drops all events at or after `frame`.
+    pub fn truncate(&mut self, frame: u64) {
+        let position = self.events.partition_point(|(at, _)| *at < frame);
+        self.events.truncate(position);
+    }
+
+    pub fn push(&mut self, frame: u64, keys: u16) {
+        let position = self.events.partition_point(|(at, _)| *at <= frame);
+        self.events.insert(position, (frame, keys));
+    }
+}
+
+/// Parses a `<frame> <keys>` line.
+pub(crate) fn parse_event(line: &str) -> Result<(u64, u16), String> {
+    let mut fields = line.split_whitespace();
+    let (Some(frame), Some(keys), None) = (fields.next(), fields.next(), fields.next()) else {
+        return Err("expected `<frame> <keys>`".to_string());
+    };
+
+    let frame = frame
+        .parse::<u64>()
+        .map_err(|_| format!("invalid frame `{}`", frame))?;
+    let keys = parse_keys(keys).ok_or_else(|| format!("invalid keys `{}`", keys))?;
+
+    Ok((frame, keys))
+}
+
+/// Parses held keys as hex digits, one per key, or `-` for none.
+pub fn parse_keys(keys: &str) -> Option<u16> {
+    if keys == "-" {
+        return Some(0);
+    }
+    if keys.is_empty() {
+        return None;
+    }
+
+    keys.chars().try_fold(0, |held, digit| {
+        digit.to_digit(16).map(|key| held | 1 << key)
+    })
+}
+
+/// Formats held keys the way [`parse_keys`] reads them.
+pub fn keys_to_string(keys: u16) -> String {
+    if keys == 0 {
+        return "-".to_string();
+    }
+
+    (0..16)
+        .filter(|key| keys & 1 << key != 0)
+        .map(|key| format!("{:X}", key))
+        .collect()
+}
+
+/// Runs `machine` until `limit` is reached, applying `script` at the start
+/// of each frame and passing the buzzer state to `audio` at the end of it.
+/// Returns the number of instructions executed.
+pub fn run(
+    machine: &mut Machine,
+    limit: RunLimit,
+    script: &KeyScript,
+    audio: &mut dyn AudioSink,
+) -> Result<u64, ExecError> {
+    let max_cycles = match limit {
+        RunLimit::Frames(frames) => frames.saturating_mul(machine.instructions_per_frame() as u64),
+        RunLimit::Cycles(cycles) => cycles,
+    };
+
+    let mut events = script.events.iter().peekable();
+    let mut cycles = 0;
+    let mut frame = 0;
+
+    while cycles < max_cycles && !machine.has_exited() {
+        while let Some((_, keys)) = events.next_if(|(at, _)| *at <= frame) {
+            machine.set_keys(*keys);
+        }
+
+        for _ in 0..machine.instructions_per_frame() {
+            if cycles == max_cycles {
+                return Ok(cycles);
+            }
+            machine.run_instruction()?;
+            cycles += 1;
+        }
+        machine.tick_timers();
+
+        audio.update(machine.sound());
+        frame += 1;
+    }
+
+    Ok(cycles)
+}
+
+/// Renders the display as text, `.` for unlit pixels and `#`, `+` and `@`
+/// for colours 1 to 3.
+pub fn display_to_ascii(ppu: &Ppu) -> String {
+    let mut output = String::with_capacity((ppu.width() + 1) * ppu.height());
+
+    for row in ppu.display.iter().take(ppu.height()) {
+        for pixel in &row[..ppu.width()] {
+            output.push(match pixel {
+                0 => '.',
+                1 => '#',
+                2 => '+',
+                _ => '@',
+            });
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Renders the display as a plain (`P1`) PBM image, with any non-zero colour
+/// as black.
+pub fn display_to_pbm(ppu: &Ppu) -> String {
+    let mut output = format!("P1\n{} {}\n", ppu.width(), ppu.height());
+
+    for row in ppu.display.iter().take(ppu.height()) {
+        for pixel in &row[..ppu.width()] {
+            output.push(if *pixel != 0 { '1' } else { '0' });
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Formats the program counter, I, V0-VF and the call stack.
+pub fn registers_to_string(machine: &Machine) -> String {
+    let registers = &machine.cpu().registers;
+    let mut output = format!(
+        "PC: {:#06X}  I: {:#06X}\n",
+        machine.cpu().program_counter.get_value(),
+        registers.get_i()
+    );
+
+    for index in 0..16 {
+        let separator = if index % 8 == 7 { '\n' } else { ' ' };
+        let _ = write!(
+            output,
+            "V{:X}: {:#04X}{}",
+            index,
+            registers.get_vn(index),
+            separator
+        );
+    }
+
+    let stack: Vec<String> = registers
+        .stack()
+        .iter()
+        .map(|address| format!("{:#06X}", address))
+        .collect();
+    let _ = writeln!(output, "Stack: [{}]", stack.join(", "));
+
+    output
+}
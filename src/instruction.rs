@@ -1,9 +1,10 @@
-use rand::Rng;
-
 use crate::cpu::Cpu;
+use crate::error::ExecError;
 use crate::keyboard::Keyboard;
 use crate::ppu::Ppu;
-use crate::ram::Ram;
+use crate::quirks::{IndexIncrement, Quirks};
+use crate::ram::{Ram, BIG_FONT_ADDRESS};
+use crate::rng::Rng;
 use crate::timer::Timer;
 
 const MASK_LSBIT: u8 = 0b0000_0001;
@@ -12,93 +13,191 @@ const MASK_MSBIT: u8 = 0b1000_0000;
 pub struct Instruction {}
 
 impl Instruction {
-    pub fn exec_0x0(cpu: &mut Cpu, ppu: &mut Ppu, nn: u8) {
-        match nn {
-            0xEE => {
+    pub fn exec_0x0(cpu: &mut Cpu, ppu: &mut Ppu, addr: u16) -> Result<(), ExecError> {
+        match addr {
+            0x0EE => {
                 // 00EE
                 // Returns from a subroutine.
-                let value = cpu.registers.stack_pop();
+                let value = cpu.registers.stack_pop()?;
                 cpu.program_counter.set_value(value);
             }
-            0xE0 => {
+            0x0E0 => {
                 // 00E0
                 // Clears the screen.
                 ppu.clear();
                 cpu.program_counter.next();
             }
-            _ => panic!("Invalid 0x00nn instruction"),
+            0x0C0..=0x0CF => {
+                // 00CN (SUPER-CHIP)
+                // Scrolls the display down by N pixels.
+                ppu.scroll_down((addr & 0x00F) as usize);
+                cpu.program_counter.next();
+            }
+            0x0D0..=0x0DF => {
+                // 00DN (XO-CHIP)
+                // Scrolls the display up by N pixels.
+                ppu.scroll_up((addr & 0x00F) as usize);
+                cpu.program_counter.next();
+            }
+            0x0FB => {
+                // 00FB (SUPER-CHIP)
+                // Scrolls the display right by 4 pixels.
+                ppu.scroll_right(4);
+                cpu.program_counter.next();
+            }
+            0x0FC => {
+                // 00FC (SUPER-CHIP)
+                // Scrolls the display left by 4 pixels.
+                ppu.scroll_left(4);
+                cpu.program_counter.next();
+            }
+            0x0FD => {
+                // 00FD (SUPER-CHIP)
+                // Exits the interpreter.
+                cpu.exited = true;
+            }
+            0x0FE => {
+                // 00FE (SUPER-CHIP)
+                // Switches to 64x32 low resolution mode.
+                ppu.set_hires(false);
+                cpu.program_counter.next();
+            }
+            0x0FF => {
+                // 00FF (SUPER-CHIP)
+                // Switches to 128x64 high resolution mode.
+                ppu.set_hires(true);
+                cpu.program_counter.next();
+            }
+            _ => {
+                return Err(ExecError::UnknownOpcode {
+                    address: cpu.program_counter.get_value(),
+                    opcode: addr,
+                })
+            }
         }
+
+        Ok(())
     }
 
-    pub fn exec_0x1(cpu: &mut Cpu, addr: u16) {
+    pub fn exec_0x1(cpu: &mut Cpu, addr: u16) -> Result<(), ExecError> {
         // 1NNN
         // Jumps to address NNN.
         cpu.program_counter.set_value(addr);
+
+        Ok(())
     }
 
-    pub fn exec_0x2(cpu: &mut Cpu, addr: u16) {
+    pub fn exec_0x2(cpu: &mut Cpu, addr: u16) -> Result<(), ExecError> {
         // 2NNN
         // Calls subroutine at NNN.
-        let value = cpu.program_counter.get_value() + 2;
-        cpu.registers.stack_push(value);
+        // A call in the last word of memory has nowhere to return to
+        let address = cpu.program_counter.get_value();
+        let value = address
+            .checked_add(2)
+            .ok_or(ExecError::PcOutOfBounds { address })?;
+        cpu.registers.stack_push(value)?;
         cpu.program_counter.set_value(addr);
+
+        Ok(())
     }
 
-    pub fn exec_0x3(cpu: &mut Cpu, nn: u8, x: u8) {
+    pub fn exec_0x3(cpu: &mut Cpu, ram: &Ram, nn: u8, x: u8) -> Result<(), ExecError> {
         // 3XNN
         // Skips the next instruction if VX equals NN (usually the
         // next instruction is a jump to skip a code block).
         let vx = cpu.registers.get_vn(x);
         if vx == nn {
-            cpu.program_counter.skip_next();
+            skip_next(cpu, ram);
         } else {
             cpu.program_counter.next();
         }
+
+        Ok(())
     }
 
-    pub fn exec_0x4(cpu: &mut Cpu, nn: u8, x: u8) {
+    pub fn exec_0x4(cpu: &mut Cpu, ram: &Ram, nn: u8, x: u8) -> Result<(), ExecError> {
         // 4XNN
         // Skips the next instruction if VX does not equal NN.
         // (usually the next instruction is a jump to skip a code block)
         let vx = cpu.registers.get_vn(x);
         if vx != nn {
-            cpu.program_counter.skip_next();
+            skip_next(cpu, ram);
         } else {
             cpu.program_counter.next();
         }
+
+        Ok(())
     }
 
-    pub fn exec_0x5(cpu: &mut Cpu, x: u8, y: u8) {
-        // 5XY0
-        // Skips the next instruction if VX equals VY (usually the next
-        // instruction is a jump to skip a code block).
-        let vx = cpu.registers.get_vn(x);
-        let vy = cpu.registers.get_vn(y);
+    pub fn exec_0x5(cpu: &mut Cpu, ram: &mut Ram, n: u8, x: u8, y: u8) -> Result<(), ExecError> {
+        match n {
+            0x0 => {
+                // 5XY0
+                // Skips the next instruction if VX equals VY (usually the next
+                // instruction is a jump to skip a code block).
+                let vx = cpu.registers.get_vn(x);
+                let vy = cpu.registers.get_vn(y);
 
-        if vx == vy {
-            cpu.program_counter.skip_next();
-        } else {
-            cpu.program_counter.next();
+                if vx == vy {
+                    skip_next(cpu, ram);
+                } else {
+                    cpu.program_counter.next();
+                }
+            }
+            0x2 => {
+                // 5XY2 (XO-CHIP)
+                // Stores VX to VY in memory, starting at address I, in descending
+                // order if X is greater than Y. I is not modified.
+                let i_reg = cpu.registers.get_i();
+                for (offset, index) in register_range(x, y).enumerate() {
+                    let vn = cpu.registers.get_vn(index);
+                    ram.write_byte(i_reg.wrapping_add(offset as u16), vn)?;
+                }
+                cpu.program_counter.next();
+            }
+            0x3 => {
+                // 5XY3 (XO-CHIP)
+                // Fills VX to VY with values from memory, starting at address I,
+                // in descending order if X is greater than Y. I is not modified.
+                let i_reg = cpu.registers.get_i();
+                for (offset, index) in register_range(x, y).enumerate() {
+                    let value = ram.read_byte(i_reg.wrapping_add(offset as u16))?;
+                    cpu.registers.set_vn(index, value);
+                }
+                cpu.program_counter.next();
+            }
+            _ => {
+                return Err(ExecError::UnknownOpcode {
+                    address: cpu.program_counter.get_value(),
+                    opcode: 0x5000 | (x as u16) << 8 | (y as u16) << 4 | n as u16,
+                })
+            }
         }
+
+        Ok(())
     }
 
-    pub fn exec_0x6(cpu: &mut Cpu, nn: u8, x: u8) {
+    pub fn exec_0x6(cpu: &mut Cpu, nn: u8, x: u8) -> Result<(), ExecError> {
         // 6XNN
         // Sets VX to NN.
         cpu.registers.set_vn(x, nn);
         cpu.program_counter.next();
+
+        Ok(())
     }
 
-    pub fn exec_0x7(cpu: &mut Cpu, nn: u8, x: u8) {
+    pub fn exec_0x7(cpu: &mut Cpu, nn: u8, x: u8) -> Result<(), ExecError> {
         // 7XNN
         // Adds NN to VX. (carry flag is not changed)
         let vx = cpu.registers.get_vn(x);
         let result = vx.wrapping_add(nn);
         cpu.registers.set_vn(x, result);
         cpu.program_counter.next();
+
+        Ok(())
     }
 
-    pub fn exec_0x8(cpu: &mut Cpu, n: u8, x: u8, y: u8, shift_quirk: bool) {
+    pub fn exec_0x8(cpu: &mut Cpu, n: u8, x: u8, y: u8, quirks: &Quirks) -> Result<(), ExecError> {
         match n {
             0x0 => {
                 // 8XY0
@@ -115,6 +214,9 @@ impl Instruction {
 
                 let result = vx | vy;
                 cpu.registers.set_vn(x, result);
+                if quirks.vf_reset {
+                    cpu.registers.set_vn(0xF, 0);
+                }
 
                 cpu.program_counter.next();
             }
@@ -125,6 +227,9 @@ impl Instruction {
                 let vy = cpu.registers.get_vn(y);
                 let result = vx & vy;
                 cpu.registers.set_vn(x, result);
+                if quirks.vf_reset {
+                    cpu.registers.set_vn(0xF, 0);
+                }
                 cpu.program_counter.next();
             }
             0x3 => {
@@ -134,6 +239,9 @@ impl Instruction {
                 let vy = cpu.registers.get_vn(y);
                 let result = vx ^ vy;
                 cpu.registers.set_vn(x, result);
+                if quirks.vf_reset {
+                    cpu.registers.set_vn(0xF, 0);
+                }
                 cpu.program_counter.next();
             }
             0x4 => {
@@ -172,7 +280,7 @@ impl Instruction {
                 // Store the value of register VY shifted right one bit in register VX
                 // Set register VF to the least significant bit prior to the shift.
 
-                if shift_quirk {
+                if quirks.shifting {
                     let vx = cpu.registers.get_vn(x);
                     let shifted_vx = vx >> 1;
 
@@ -212,7 +320,7 @@ impl Instruction {
                 // 8XYE
                 // Store the value of register VY shifted left one bit in register VX
                 // Set register VF to the most significant bit prior to the shift
-                if shift_quirk {
+                if quirks.shifting {
                     let vx = cpu.registers.get_vn(x);
                     let shifted_vx = vx << 1;
 
@@ -232,11 +340,18 @@ impl Instruction {
 
                 cpu.program_counter.next();
             }
-            _ => panic!("Invalid 0x8xxn instruction"),
+            _ => {
+                return Err(ExecError::UnknownOpcode {
+                    address: cpu.program_counter.get_value(),
+                    opcode: 0x8000 | (x as u16) << 8 | (y as u16) << 4 | n as u16,
+                })
+            }
         }
+
+        Ok(())
     }
 
-    pub fn exec_0x9(cpu: &mut Cpu, x: u8, y: u8) {
+    pub fn exec_0x9(cpu: &mut Cpu, ram: &Ram, x: u8, y: u8) -> Result<(), ExecError> {
         // 9XY0
         // Skips the next instruction if VX does not equal VY (usually the next
         // instruction is a jump to skip a code block).
@@ -244,44 +359,60 @@ impl Instruction {
         let vy = cpu.registers.get_vn(y);
 
         if vx != vy {
-            cpu.program_counter.skip_next();
+            skip_next(cpu, ram);
         } else {
             cpu.program_counter.next();
         }
+
+        Ok(())
     }
 
-    pub fn exec_0xa(cpu: &mut Cpu, addr: u16) {
+    pub fn exec_0xa(cpu: &mut Cpu, addr: u16) -> Result<(), ExecError> {
         // ANNN
         // Sets I to the address NNN.
         cpu.registers.set_i(addr);
         cpu.program_counter.next();
+
+        Ok(())
     }
 
-    pub fn exec_0xb(cpu: &mut Cpu, addr: u16, x: u8, jump_quirk: bool) {
+    pub fn exec_0xb(cpu: &mut Cpu, addr: u16, x: u8, quirks: &Quirks) -> Result<(), ExecError> {
         // BNNN
         // Jumps to the address NNN plus V0.
-        if jump_quirk {
+        if quirks.jumping {
             let vx = cpu.registers.get_vn(x);
             cpu.program_counter.set_value(addr + vx as u16);
         } else {
             let v0 = cpu.registers.get_vn(0);
             cpu.program_counter.set_value(addr + v0 as u16);
         }
+
+        Ok(())
     }
 
-    pub fn exec_0xc(cpu: &mut Cpu, nn: u8, x: u8) {
+    pub fn exec_0xc(cpu: &mut Cpu, rng: &mut Rng, nn: u8, x: u8) -> Result<(), ExecError> {
         // CXNN
         // Sets VX to the result of a bitwise and operation on a random number
         // (Typically: 0 to 255) and NN.
-        let rng = rand::thread_rng().gen_range(0..=255);
+        let random = rng.next_byte();
 
-        let result = rng & nn;
+        let result = random & nn;
         cpu.registers.set_vn(x, result);
 
         cpu.program_counter.next();
+
+        Ok(())
     }
 
-    pub fn exec_0xd(cpu: &mut Cpu, ram: &mut Ram, ppu: &mut Ppu, n: u8, x: u8, y: u8) {
+    pub fn exec_0xd(
+        cpu: &mut Cpu,
+        ram: &mut Ram,
+        ppu: &mut Ppu,
+        n: u8,
+        x: u8,
+        y: u8,
+        quirks: &Quirks,
+    ) -> Result<(), ExecError> {
         // DXYN
         // Draws a sprite at coordinate (VX, VY) that has a width of 8 pixels
         // and a height of N pixels. Each row of 8 pixels is read as bit-coded
@@ -289,16 +420,16 @@ impl Instruction {
         // execution of this instruction. As described above, VF is set to 1
         // if any screen pixels are flipped from set to unset when the sprite
         // is drawn, and to 0 if that does not happen.
+        // DXY0 (SUPER-CHIP) draws a 16x16 sprite with two bytes per row instead.
         let vx = cpu.registers.get_vn(x);
         let vy = cpu.registers.get_vn(y);
         let i = cpu.registers.get_i();
-        let sprite_height = n;
-        let sprite_width = 8_u8;
+        let (sprite_height, sprite_width) = if n == 0 { (16, 16) } else { (n, 8_u8) };
 
         // Clear vf
         cpu.registers.set_vn(0xF, 0);
 
-        ppu.render_pixels(vx, vy, i, sprite_height, sprite_width, ram);
+        ppu.render_pixels(vx, vy, i, sprite_height, sprite_width, quirks.clipping, ram)?;
 
         if ppu.pixel_flipped {
             cpu.registers.set_vn(0xF, 1);
@@ -306,10 +437,22 @@ impl Instruction {
             cpu.registers.set_vn(0xF, 0);
         }
 
+        if quirks.display_wait {
+            cpu.vblank_wait = true;
+        }
+
         cpu.program_counter.next();
+
+        Ok(())
     }
 
-    pub fn exec_0xe(cpu: &mut Cpu, nn: u8, x: u8, keyboard: &mut Keyboard) {
+    pub fn exec_0xe(
+        cpu: &mut Cpu,
+        ram: &Ram,
+        nn: u8,
+        x: u8,
+        keyboard: &mut Keyboard,
+    ) -> Result<(), ExecError> {
         match nn {
             0xA1 => {
                 // EXA1
@@ -317,14 +460,10 @@ impl Instruction {
                 // not pressed (usually the next instruction is a jump to skip a code block).
                 let vx = cpu.registers.get_vn(x);
 
-                if let Some(key) = keyboard.key {
-                    if key == vx {
-                        cpu.program_counter.next();
-                    } else {
-                        cpu.program_counter.skip_next();
-                    }
+                if keyboard.is_pressed(vx) {
+                    cpu.program_counter.next();
                 } else {
-                    cpu.program_counter.skip_next();
+                    skip_next(cpu, ram);
                 }
             }
             0x9E => {
@@ -333,34 +472,73 @@ impl Instruction {
                 // pressed (usually the next instruction is a jump to skip a code block).
                 let vx = cpu.registers.get_vn(x);
 
-                if let Some(key) = keyboard.key {
-                    if key == vx {
-                        cpu.program_counter.skip_next();
-                    } else {
-                        cpu.program_counter.next();
-                    }
+                if keyboard.is_pressed(vx) {
+                    skip_next(cpu, ram);
                 } else {
                     cpu.program_counter.next();
                 }
             }
-            _ => panic!("Invalid 0xExnn instruction"),
+            _ => {
+                return Err(ExecError::UnknownOpcode {
+                    address: cpu.program_counter.get_value(),
+                    opcode: 0xE000 | (x as u16) << 8 | nn as u16,
+                })
+            }
         }
+
+        Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn exec_0xf(
         cpu: &mut Cpu,
         ram: &mut Ram,
+        ppu: &mut Ppu,
         timer: &mut Timer,
         keyboard: &mut Keyboard,
         nn: u8,
         x: u8,
-    ) {
+        quirks: &Quirks,
+    ) -> Result<(), ExecError> {
         match nn {
+            0x00 if x == 0 => {
+                // F000 NNNN (XO-CHIP)
+                // Sets I to the 16-bit address NNNN stored in the next two bytes.
+                let pc = cpu.program_counter.get_value();
+                let address = ram.get_instruction(pc.wrapping_add(2))?;
+                cpu.registers.set_i(address);
+                cpu.program_counter.skip_next();
+            }
+            0x01 => {
+                // FN01 (XO-CHIP)
+                // Selects the bitplanes N (0-3) that drawing, clearing and
+                // scrolling apply to.
+                ppu.select_planes(x);
+                cpu.program_counter.next();
+            }
+            0x02 if x == 0 => {
+                // F002 (XO-CHIP)
+                // Loads the 16-byte audio pattern buffer from memory at I.
+                let i = cpu.registers.get_i();
+                let mut pattern = [0; 16];
+                for (offset, byte) in pattern.iter_mut().enumerate() {
+                    *byte = ram.read_byte(i.wrapping_add(offset as u16))?;
+                }
+                timer.set_audio_pattern(pattern);
+                cpu.program_counter.next();
+            }
+            0x3A => {
+                // FX3A (XO-CHIP)
+                // Sets the audio pattern playback pitch to VX.
+                let vx = cpu.registers.get_vn(x);
+                timer.set_pitch(vx);
+                cpu.program_counter.next();
+            }
             0x0A => {
                 // FX0A
                 // A key press is awaited, and then stored in VX (blocking operation, all
-                // instruction halted until next key event).
-                if let Some(key) = keyboard.key {
+                // instruction halted until the key is pressed and released again).
+                if let Some(key) = keyboard.wait_for_key() {
                     cpu.registers.set_vn(x, key);
                     cpu.program_counter.next();
                 }
@@ -377,7 +555,7 @@ impl Instruction {
                 // Adds VX to I. VF is not affected.
                 let vx = cpu.registers.get_vn(x);
                 let i = cpu.registers.get_i();
-                let result = vx as u16 + i;
+                let result = i.wrapping_add(vx as u16);
                 cpu.registers.set_i(result);
                 cpu.program_counter.next();
             }
@@ -391,6 +569,8 @@ impl Instruction {
             0x18 => {
                 // FX18
                 // Sets the sound timer to VX.
+                let vx = cpu.registers.get_vn(x);
+                timer.set_sound_timer(vx);
                 cpu.program_counter.next();
             }
             0x29 => {
@@ -401,6 +581,14 @@ impl Instruction {
                 cpu.registers.set_i(vx as u16 * 5);
                 cpu.program_counter.next();
             }
+            0x30 => {
+                // FX30 (SUPER-CHIP)
+                // Sets I to the location of the 8x10 sprite for the character in VX.
+                let vx = cpu.registers.get_vn(x);
+                cpu.registers
+                    .set_i(BIG_FONT_ADDRESS + (vx & 0xF) as u16 * 10);
+                cpu.program_counter.next();
+            }
             0x33 => {
                 // FX33
                 // Stores the binary-coded decimal representation of VX,
@@ -413,22 +601,24 @@ impl Instruction {
                 let tenth = (vx / 10) % 10;
                 let first = vx % 10;
 
-                ram.write_byte(i, hundreth);
-                ram.write_byte(i + 1, tenth);
-                ram.write_byte(i + 2, first);
+                ram.write_byte(i, hundreth)?;
+                ram.write_byte(i.wrapping_add(1), tenth)?;
+                ram.write_byte(i.wrapping_add(2), first)?;
 
                 cpu.program_counter.next();
             }
             0x55 => {
                 // FX55
                 // Stores from V0 to VX (including VX) in memory, starting at address I.
-                // The offset from I is increased by 1 for each value written, but I
-                // itself is left unmodified.
+                // The offset from I is increased by 1 for each value written; where I
+                // itself ends up depends on the platform.
                 let i_reg = cpu.registers.get_i();
                 for i in 0..=x {
                     let vn = cpu.registers.get_vn(i);
-                    ram.write_byte(i_reg + i as u16, vn);
+                    ram.write_byte(i_reg.wrapping_add(i as u16), vn)?;
                 }
+                cpu.registers
+                    .set_i(index_after_transfer(i_reg, x, quirks.index_increment));
 
                 cpu.program_counter.next();
             }
@@ -436,16 +626,69 @@ impl Instruction {
                 // FX65
                 // Fills from V0 to VX (including VX) with values from memory,
                 // starting at address I. The offset from I is increased by 1 for
-                // each value read.
+                // each value read; where I itself ends up depends on the platform.
                 let i_reg = cpu.registers.get_i();
                 for i in 0..=x {
-                    cpu.registers.set_vn(i, ram.read_byte(i_reg + i as u16));
-                    cpu.registers.set_i(i_reg + 1);
+                    cpu.registers
+                        .set_vn(i, ram.read_byte(i_reg.wrapping_add(i as u16))?);
                 }
+                cpu.registers
+                    .set_i(index_after_transfer(i_reg, x, quirks.index_increment));
 
                 cpu.program_counter.next();
             }
-            _ => panic!("Invalid 0xFxnn instruction"),
+            0x75 => {
+                // FX75 (SUPER-CHIP)
+                // Stores V0 to VX (including VX) in the RPL user flags.
+                for i in 0..=x {
+                    let vn = cpu.registers.get_vn(i);
+                    cpu.registers.set_rpl_flag(i, vn);
+                }
+                cpu.program_counter.next();
+            }
+            0x85 => {
+                // FX85 (SUPER-CHIP)
+                // Fills V0 to VX (including VX) from the RPL user flags.
+                for i in 0..=x {
+                    let flag = cpu.registers.get_rpl_flag(i);
+                    cpu.registers.set_vn(i, flag);
+                }
+                cpu.program_counter.next();
+            }
+            _ => {
+                return Err(ExecError::UnknownOpcode {
+                    address: cpu.program_counter.get_value(),
+                    opcode: 0xF000 | (x as u16) << 8 | nn as u16,
+                })
+            }
         }
+
+        Ok(())
+    }
+}
+
+fn index_after_transfer(i: u16, x: u8, increment: IndexIncrement) -> u16 {
+    match increment {
+        IndexIncrement::XPlusOne => i.wrapping_add(x as u16 + 1),
+        IndexIncrement::X => i.wrapping_add(x as u16),
+        IndexIncrement::Unchanged => i,
     }
 }
+
+/// Skips the next instruction, which is four bytes long if it is the XO-CHIP
+/// `F000 NNNN`.
+fn skip_next(cpu: &mut Cpu, ram: &Ram) {
+    let next = cpu.program_counter.get_value().wrapping_add(2);
+
+    if ram.get_instruction(next).ok() == Some(0xF000) {
+        cpu.program_counter.set_value(next.wrapping_add(4));
+    } else {
+        cpu.program_counter.skip_next();
+    }
+}
+
+/// The registers from VX to VY, counting down if X is greater than Y.
+fn register_range(x: u8, y: u8) -> impl Iterator<Item = u8> {
+    let count = x.abs_diff(y) + 1;
+    (0..count).map(move |offset| if x <= y { x + offset } else { x - offset })
+}
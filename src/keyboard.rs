@@ -1,158 +1,87 @@
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::EventPump;
+use crate::state::{StateError, StateReader, StateWriter};
 
+/// State of the 16-key hex keypad.
 pub struct Keyboard {
-    pub key: Option<u8>,
-    pub escape_pressed: bool,
+    /// One bit per key, bit `n` set while key `n` is held.
+    pressed: u16,
+    /// Key that FX0A saw go down and is waiting to see released.
+    awaiting_release: Option<u8>,
 }
 
 impl Keyboard {
     pub fn new() -> Self {
         Self {
-            key: None,
-            escape_pressed: false,
+            pressed: 0,
+            awaiting_release: None,
         }
     }
 
-    pub fn set_key(&mut self, event_pump: &mut EventPump) {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => self.escape_pressed = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num1),
-                    ..
-                } => self.key = Some(1),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num2),
-                    ..
-                } => self.key = Some(2),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num3),
-                    ..
-                } => self.key = Some(3),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num4),
-                    ..
-                } => self.key = Some(4),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num5),
-                    ..
-                } => self.key = Some(5),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num6),
-                    ..
-                } => self.key = Some(6),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num7),
-                    ..
-                } => self.key = Some(7),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num8),
-                    ..
-                } => self.key = Some(8),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num9),
-                    ..
-                } => self.key = Some(9),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num0),
-                    ..
-                } => self.key = Some(0),
-                Event::KeyDown {
-                    keycode: Some(Keycode::A),
-                    ..
-                } => self.key = Some(10),
-                Event::KeyDown {
-                    keycode: Some(Keycode::B),
-                    ..
-                } => self.key = Some(11),
-                Event::KeyDown {
-                    keycode: Some(Keycode::C),
-                    ..
-                } => self.key = Some(12),
-                Event::KeyDown {
-                    keycode: Some(Keycode::D),
-                    ..
-                } => self.key = Some(13),
-                Event::KeyDown {
-                    keycode: Some(Keycode::E),
-                    ..
-                } => self.key = Some(14),
-                Event::KeyDown {
-                    keycode: Some(Keycode::F),
-                    ..
-                } => self.key = Some(15),
-                Event::KeyUp {
-                    keycode: Some(Keycode::Num1),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::Num2),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::Num3),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::Num4),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::Num5),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::Num6),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::Num7),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::Num8),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::Num9),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::Num0),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::A),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::B),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::C),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::D),
-                    ..
-                }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::E),
-                    ..
+    pub fn is_pressed(&self, key: u8) -> bool {
+        self.pressed & key_bit(key) != 0
+    }
+
+    /// The held keys, bit `n` for key `n`.
+    pub fn pressed(&self) -> u16 {
+        self.pressed
+    }
+
+    pub fn set_pressed(&mut self, keys: u16) {
+        self.pressed = keys;
+    }
+
+    pub fn press(&mut self, key: u8) {
+        self.pressed |= key_bit(key);
+    }
+
+    pub fn release(&mut self, key: u8) {
+        self.pressed &= !key_bit(key);
+    }
+
+    /// Advances an FX0A wait. Like the COSMAC VIP, a key only counts once it
+    /// has been pressed and released again; returns it at that point.
+    pub(crate) fn wait_for_key(&mut self) -> Option<u8> {
+        match self.awaiting_release {
+            Some(key) if !self.is_pressed(key) => {
+                self.awaiting_release = None;
+                Some(key)
+            }
+            Some(_) => None,
+            None => {
+                if self.pressed != 0 {
+                    self.awaiting_release = Some(self.pressed.trailing_zeros() as u8);
                 }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::F),
-                    ..
-                } => self.key = None,
-                _ => {}
-            };
+                None
+            }
         }
     }
+
+    pub(crate) fn save(&self, writer: &mut StateWriter) {
+        writer.u16(self.pressed);
+        // 0xFF stands for no key awaiting release
+        writer.u8(self.awaiting_release.unwrap_or(0xFF));
+    }
+
+    pub(crate) fn load(reader: &mut StateReader) -> Result<Self, StateError> {
+        let pressed = reader.u16()?;
+        let awaiting_release = match reader.u8()? {
+            0xFF => None,
+            key @ 0x0..=0xF => Some(key),
+            _ => return Err(StateError::Corrupt),
+        };
+
+        Ok(Self {
+            pressed,
+            awaiting_release,
+        })
+    }
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn key_bit(key: u8) -> u16 {
+    1 << (key & 0xF)
 }
@@ -0,0 +1,140 @@
+//! Mapping from physical keyboard keys and controller buttons to the 16-key
+//! hex keypad.
+//!
+//! Keys and buttons are identified by name, as SDL reports them (`"1"`,
+//! `"Q"`, `"Space"`, `"Keypad 5"`, `"a"`, `"dpup"`), so the mapping stays
+//! independent of any frontend. Names compare case-insensitively.
+
+/// The COSMAC VIP keypad laid over the left-hand block of a QWERTY
+/// keyboard:
+///
+/// ```text
+/// 1 2 3 C        1 2 3 4
+/// 4 5 6 D   <-   Q W E R
+/// 7 8 9 E        A S D F
+/// A 0 B F        Z X C V
+/// ```
+const COSMAC: [(&str, u8); 16] = [
+    ("1", 0x1),
+    ("2", 0x2),
+    ("3", 0x3),
+    ("4", 0xC),
+    ("Q", 0x4),
+    ("W", 0x5),
+    ("E", 0x6),
+    ("R", 0xD),
+    ("A", 0x7),
+    ("S", 0x8),
+    ("D", 0x9),
+    ("F", 0xE),
+    ("Z", 0xA),
+    ("X", 0x0),
+    ("C", 0xB),
+    ("V", 0xF),
+];
+
+/// Each hex digit on the keyboard key with the same label.
+const HEX: [(&str, u8); 16] = [
+    ("0", 0x0),
+    ("1", 0x1),
+    ("2", 0x2),
+    ("3", 0x3),
+    ("4", 0x4),
+    ("5", 0x5),
+    ("6", 0x6),
+    ("7", 0x7),
+    ("8", 0x8),
+    ("9", 0x9),
+    ("A", 0xA),
+    ("B", 0xB),
+    ("C", 0xC),
+    ("D", 0xD),
+    ("E", 0xE),
+    ("F", 0xF),
+];
+
+/// Controller buttons for the keys most games move and fire with: 2, 4, 6
+/// and 8 as directions and 5 as the action button.
+const GAMEPAD: [(&str, u8); 6] = [
+    ("dpup", 0x2),
+    ("dpdown", 0x8),
+    ("dpleft", 0x4),
+    ("dpright", 0x6),
+    ("a", 0x5),
+    ("b", 0x5),
+];
+
+/// Bindings from key or button names to hex keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap {
+    bindings: Vec<(String, u8)>,
+}
+
+impl KeyMap {
+    pub const LAYOUT_NAMES: [&'static str; 2] = ["cosmac", "hex"];
+
+    /// A map with no bindings.
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// The COSMAC VIP keypad on the 1234/QWER/ASDF/ZXCV block.
+    pub fn cosmac() -> Self {
+        Self::from_table(&COSMAC)
+    }
+
+    /// The keys `0`-`9` and `A`-`F` mapped to their own value.
+    pub fn hex() -> Self {
+        Self::from_table(&HEX)
+    }
+
+    /// Controller D-pad on 2/4/6/8 and the face buttons on 5.
+    pub fn gamepad() -> Self {
+        Self::from_table(&GAMEPAD)
+    }
+
+    /// Looks up a built-in keyboard layout by name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "cosmac" => Some(Self::cosmac()),
+            "hex" => Some(Self::hex()),
+            _ => None,
+        }
+    }
+
+    fn from_table(table: &[(&str, u8)]) -> Self {
+        let mut map = Self::new();
+        for (name, key) in table {
+            map.bind(name, *key);
+        }
+        map
+    }
+
+    /// Binds the keyboard key `name` to hex key `key`, replacing any
+    /// existing binding for `name`.
+    pub fn bind(&mut self, name: &str, key: u8) {
+        self.bindings
+            .retain(|(bound, _)| !bound.eq_ignore_ascii_case(name));
+        self.bindings.push((name.to_string(), key & 0xF));
+    }
+
+    /// The hex key bound to the keyboard key `name`.
+    pub fn key_for(&self, name: &str) -> Option<u8> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound.eq_ignore_ascii_case(name))
+            .map(|(_, key)| *key)
+    }
+
+    pub fn bindings(&self) -> &[(String, u8)] {
+        &self.bindings
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::cosmac()
+    }
+}
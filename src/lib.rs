@@ -0,0 +1,46 @@
+//! A CHIP-8 emulator core.
+//!
+//! The [`Machine`] ties together the CPU, memory, display, timers and keypad
+//! and is the entry point for frontends: load a [`Rom`], call
+//! [`Machine::run_instruction`] in a loop, feed key input with
+//! [`Machine::press_key`] and [`Machine::release_key`] and read the
+//! framebuffer with [`Machine::display`].
+//!
+//! The core has no dependency on SDL2; the bundled SDL2 frontend lives in the
+//! `chip8` binary behind the `sdl` cargo feature.
+
+pub mod assembler;
+pub mod audio;
+pub mod config;
+pub mod cpu;
+pub mod debugger;
+pub mod disassembler;
+mod error;
+pub mod headless;
+mod instruction;
+pub mod keyboard;
+pub mod keymap;
+pub mod machine;
+pub mod movie;
+pub mod palette;
+pub mod ppu;
+pub mod program_counter;
+pub mod quirks;
+pub mod ram;
+pub mod registers;
+pub mod rng;
+pub mod rewind;
+pub mod rom;
+pub mod romdb;
+pub mod scheduler;
+pub mod state;
+pub mod timer;
+pub mod trace;
+pub mod viewport;
+
+pub use error::ExecError;
+pub use machine::Machine;
+pub use ppu::{HIRES_HEIGHT, HIRES_WIDTH, SCREEN_HEIGHT, SCREEN_WIDTH};
+pub use quirks::Quirks;
+pub use rng::Rng;
+pub use rom::Rom;
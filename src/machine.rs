@@ -1,49 +1,317 @@
+use crate::audio::{AudioPattern, Sound};
 use crate::cpu::Cpu;
+use crate::error::ExecError;
 use crate::keyboard::Keyboard;
-use crate::ppu::Ppu;
-use crate::ram::Ram;
+use crate::ppu::{Ppu, HIRES_HEIGHT, HIRES_WIDTH};
+use crate::quirks::Quirks;
+use crate::ram::{Ram, EXTENDED_RAM_SIZE, RAM_SIZE};
+use crate::rng::Rng;
 use crate::rom::Rom;
+use crate::state::{StateError, StateReader, StateWriter, MAGIC, VERSION};
 use crate::timer::Timer;
+use crate::trace::{TraceState, Tracer};
 
+/// Address at which programs are loaded and execution starts.
 pub const ROM_START_ADDRESS: u16 = 0x200;
 
+/// Default number of instructions executed per 60 Hz frame.
+pub const INSTRUCTIONS_PER_FRAME: usize = 10;
+
+/// A complete CHIP-8 system.
 pub struct Machine {
     cpu: Cpu,
     ram: Ram,
-    pub ppu: Ppu,
+    ppu: Ppu,
     timer: Timer,
-    pub keyboard: Keyboard,
-    shift_quirk: bool,
-    jump_quirk: bool,
+    keyboard: Keyboard,
+    rng: Rng,
+    quirks: Quirks,
+    instructions_per_frame: usize,
+    cycles: u64,
+    frames: u64,
+    rom_hash: [u8; 20],
+    tracer: Option<Tracer>,
 }
 
 impl Machine {
-    pub fn new(shift_quirk: bool, jump_quirk: bool) -> Self {
+    /// Creates a machine with cleared memory and registers and a randomly
+    /// seeded [`Rng`], emulating the interpreter described by `quirks`.
+    pub fn new(quirks: Quirks) -> Self {
         Self {
             cpu: Cpu::new(),
-            ram: Ram::new(),
+            ram: Ram::with_size(memory_size(&quirks)),
             ppu: Ppu::new(),
             timer: Timer::new(),
             keyboard: Keyboard::new(),
-            shift_quirk,
-            jump_quirk,
+            rng: Rng::from_entropy(),
+            quirks,
+            instructions_per_frame: INSTRUCTIONS_PER_FRAME,
+            cycles: 0,
+            frames: 0,
+            rom_hash: Rom { data: Vec::new() }.sha1(),
+            tracer: None,
         }
     }
 
-    pub fn load_rom(&mut self, rom: Rom) {
+    /// Copies the ROM into memory starting at [`ROM_START_ADDRESS`].
+    ///
+    /// Fails with [`ExecError::MemoryOutOfBounds`] if the ROM does not fit.
+    pub fn load_rom(&mut self, rom: Rom) -> Result<(), ExecError> {
         for (index, byte) in rom.data.iter().enumerate() {
-            self.ram.write_byte(index as u16 + ROM_START_ADDRESS, *byte);
+            let address = u16::try_from(index + ROM_START_ADDRESS as usize)
+                .map_err(|_| ExecError::MemoryOutOfBounds { address: u16::MAX })?;
+            self.ram.write_byte(address, *byte)?;
+        }
+        self.rom_hash = rom.sha1();
+
+        Ok(())
+    }
+
+    /// SHA-1 of the loaded ROM, used to match save states to their game.
+    pub fn rom_hash(&self) -> [u8; 20] {
+        self.rom_hash
+    }
+
+    /// Serializes the complete machine state: registers, program counter,
+    /// memory, display, timers, keypad, quirks and RNG. The tracer is not
+    /// part of the state.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.bytes(&MAGIC);
+        writer.u16(VERSION);
+        writer.bytes(&self.rom_hash);
+
+        self.cpu.save(&mut writer);
+        self.ram.save(&mut writer);
+        self.ppu.save(&mut writer);
+        self.timer.save(&mut writer);
+        self.keyboard.save(&mut writer);
+        self.quirks.save(&mut writer);
+        self.rng.save(&mut writer);
+        writer.u64(self.cycles);
+        writer.u64(self.frames);
+
+        writer.into_bytes()
+    }
+
+    /// Restores a state written by [`Machine::save_state`]. Fails without
+    /// changing the machine if the state is damaged, has another format
+    /// version or was taken with a different ROM loaded.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let mut reader = StateReader::new(state);
+        if reader.array::<4>() != Ok(MAGIC) {
+            return Err(StateError::NotAState);
+        }
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+        if reader.array::<20>()? != self.rom_hash {
+            return Err(StateError::RomMismatch);
+        }
+
+        let cpu = Cpu::load(&mut reader)?;
+        let ram = Ram::load(&mut reader)?;
+        let ppu = Ppu::load(&mut reader)?;
+        let timer = Timer::load(&mut reader)?;
+        let keyboard = Keyboard::load(&mut reader)?;
+        let quirks = Quirks::load(&mut reader)?;
+        let rng = Rng::load(&mut reader)?;
+        let cycles = reader.u64()?;
+        let frames = reader.u64()?;
+        if !reader.is_empty() || ram.size() != memory_size(&quirks) {
+            return Err(StateError::Corrupt);
         }
+
+        self.cpu = cpu;
+        self.ram = ram;
+        self.ppu = ppu;
+        self.timer = timer;
+        self.keyboard = keyboard;
+        self.quirks = quirks;
+        self.rng = rng;
+        self.cycles = cycles;
+        self.frames = frames;
+
+        Ok(())
     }
 
-    pub fn run_instruction(&mut self) {
+    /// Fetches, decodes and executes the instruction at the program counter.
+    ///
+    /// On error the program counter is left at the faulting instruction, so
+    /// calling this again reports the same error.
+    pub fn run_instruction(&mut self) -> Result<(), ExecError> {
+        let stalled = self.cpu.vblank_wait || self.cpu.exited;
+        let before = match &self.tracer {
+            Some(tracer) if !stalled => tracer.before(&self.cpu, &self.ram, &self.timer),
+            _ => None,
+        };
+
         self.cpu.execute_instruction(
             &mut self.ram,
             &mut self.ppu,
             &mut self.timer,
             &mut self.keyboard,
-            self.shift_quirk,
-            self.jump_quirk,
-        );
+            &mut self.rng,
+            &self.quirks,
+        )?;
+        self.cycles += 1;
+
+        if let (Some(tracer), Some(before)) = (&mut self.tracer, before) {
+            let after = TraceState::capture(&self.cpu, &self.ram, &self.timer);
+            tracer.record(self.cycles, &before, &after);
+        }
+
+        Ok(())
+    }
+
+    /// Number of instructions executed since the machine was created,
+    /// including ones stalled by the display wait quirk.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Number of 60 Hz frames emulated so far, counted by
+    /// [`Machine::tick_timers`].
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Starts writing a trace record for every executed instruction, or
+    /// stops tracing with `None`.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    /// Detaches the tracer so it can be [finished](Tracer::finish).
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    /// Replaces the random number source used by `CXNN`.
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    /// Reseeds the random number source, making `CXNN` reproducible.
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = Rng::from_seed(seed);
+    }
+
+    /// Number of instructions [`Machine::run_frame`] executes.
+    pub fn instructions_per_frame(&self) -> usize {
+        self.instructions_per_frame
+    }
+
+    /// Sets the emulation speed. SUPER-CHIP and XO-CHIP programs often
+    /// expect far more than the default; values below 1 are raised to 1.
+    pub fn set_instructions_per_frame(&mut self, instructions: usize) {
+        self.instructions_per_frame = instructions.max(1);
+    }
+
+    /// Runs one 60 Hz frame: a frame's worth of instructions followed by a
+    /// timer tick. Stops at the first error without ticking the timers.
+    pub fn run_frame(&mut self) -> Result<(), ExecError> {
+        for _ in 0..self.instructions_per_frame {
+            self.run_instruction()?;
+        }
+        self.tick_timers();
+
+        Ok(())
+    }
+
+    /// Decrements the delay and sound timers and ends a pending display wait.
+    /// Call once per 60 Hz frame of emulated time when driving the machine
+    /// with [`Machine::run_instruction`].
+    pub fn tick_timers(&mut self) {
+        self.frames += 1;
+        self.timer.tick();
+        self.cpu.vblank_wait = false;
+    }
+
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    /// Changes the emulated interpreter, growing or shrinking memory if
+    /// [`Quirks::extended_memory`] changed.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.ram.resize(memory_size(&quirks));
+        self.quirks = quirks;
+    }
+
+    /// The framebuffer, indexed `[y][x]`, with `1` for a lit pixel. Only the
+    /// top left [`Machine::resolution`] pixels are in use.
+    pub fn display(&self) -> &[[u8; HIRES_WIDTH]; HIRES_HEIGHT] {
+        &self.ppu.display
+    }
+
+    /// The current display size as `(width, height)`.
+    pub fn resolution(&self) -> (usize, usize) {
+        (self.ppu.width(), self.ppu.height())
+    }
+
+    /// Whether the program has ended with `00FD`.
+    pub fn has_exited(&self) -> bool {
+        self.cpu.exited
+    }
+
+    /// Whether the buzzer is on, i.e. the sound timer is non-zero.
+    pub fn sound_active(&self) -> bool {
+        self.timer.get_sound_timer() > 0
+    }
+
+    /// What the buzzer is currently playing.
+    pub fn sound(&self) -> Sound {
+        if !self.sound_active() {
+            return Sound::Silent;
+        }
+
+        match self.timer.get_audio_pattern() {
+            Some(buffer) => Sound::Pattern(AudioPattern {
+                buffer,
+                pitch: self.timer.get_pitch(),
+            }),
+            None => Sound::Beep,
+        }
+    }
+
+    /// Marks hex key `key` (`0x0`-`0xF`) as held.
+    pub fn press_key(&mut self, key: u8) {
+        self.keyboard.press(key);
+    }
+
+    /// Marks hex key `key` (`0x0`-`0xF`) as released.
+    pub fn release_key(&mut self, key: u8) {
+        self.keyboard.release(key);
+    }
+
+    /// Sets all held keys at once, bit `n` for key `n`.
+    pub fn set_keys(&mut self, keys: u16) {
+        self.keyboard.set_pressed(keys);
+    }
+
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub fn ram(&self) -> &Ram {
+        &self.ram
+    }
+
+    pub fn ppu(&self) -> &Ppu {
+        &self.ppu
+    }
+
+    pub fn keyboard(&self) -> &Keyboard {
+        &self.keyboard
+    }
+}
+
+fn memory_size(quirks: &Quirks) -> usize {
+    if quirks.extended_memory {
+        EXTENDED_RAM_SIZE
+    } else {
+        RAM_SIZE
     }
 }
@@ -0,0 +1,247 @@
+//! Input recordings that replay a play session exactly.
+//!
+//! Emulation is deterministic given the ROM, quirks, speed, RNG seed and key
+//! input, so a movie only needs to store those: the key changes with the
+//! frame number they happened on, and a header identifying the rest.
+//!
+//! ```text
+//! chip8-movie 1
+//! rom 2bb2c6b4d3e5a1f4...
+//! seed 1234
+//! quirks cosmac-vip
+//! ipf 10
+//! frames 3600
+//! 120 5
+//! 135 -
+//! ```
+//!
+//! Key lines use the same `<frame> <keys>` format as a [`KeyScript`].
+
+use std::fmt;
+
+use crate::headless::{self, KeyScript};
+use crate::machine::{Machine, INSTRUCTIONS_PER_FRAME};
+use crate::quirks::{IndexIncrement, Quirks};
+
+/// Format version written on the first line.
+pub const MOVIE_VERSION: u32 = 1;
+
+/// A recorded session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    /// SHA-1 of the ROM the movie was recorded with.
+    pub rom_hash: [u8; 20],
+    pub seed: u64,
+    pub quirks: Quirks,
+    pub instructions_per_frame: usize,
+    /// Length of the recording in frames.
+    pub frames: u64,
+    pub keys: KeyScript,
+}
+
+impl Movie {
+    pub fn new(rom_hash: [u8; 20], seed: u64, quirks: Quirks) -> Self {
+        Self {
+            rom_hash,
+            seed,
+            quirks,
+            instructions_per_frame: INSTRUCTIONS_PER_FRAME,
+            frames: 0,
+            keys: KeyScript::new(),
+        }
+    }
+
+    /// Records the keys held during `frame`. Call once per frame before it
+    /// runs; only changes are stored. Anything recorded for `frame` or later
+    /// is discarded first, so recording continues correctly after the
+    /// machine is rewound to an earlier frame of the same session.
+    pub fn record(&mut self, frame: u64, keys: u16) {
+        self.keys.truncate(frame);
+        if self.keys.keys_at(frame) != keys {
+            self.keys.push(frame, keys);
+        }
+        self.frames = frame + 1;
+    }
+
+    /// Prepares a machine that has just loaded the ROM for replay: checks
+    /// the ROM matches and applies the recorded quirks, speed and seed.
+    pub fn apply(&self, machine: &mut Machine) -> Result<(), String> {
+        if machine.rom_hash() != self.rom_hash {
+            return Err("movie was recorded with a different ROM".to_string());
+        }
+        machine.set_quirks(self.quirks);
+        machine.set_instructions_per_frame(self.instructions_per_frame);
+        machine.seed_rng(self.seed);
+        Ok(())
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut lines = source
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        match lines.next() {
+            Some((_, line)) if line == format!("chip8-movie {}", MOVIE_VERSION) => {}
+            Some((number, line)) if line.starts_with("chip8-movie ") => {
+                return Err(format!("line {}: unsupported movie version", number))
+            }
+            _ => return Err("not a chip8 movie".to_string()),
+        }
+
+        let mut rom_hash = None;
+        let mut seed = None;
+        let mut quirks = None;
+        // Movies written before the speed was configurable ran at the default
+        let mut instructions_per_frame = INSTRUCTIONS_PER_FRAME;
+        let mut frames = None;
+        let mut keys = KeyScript::new();
+
+        for (number, line) in lines {
+            let error = |message: String| format!("line {}: {}", number, message);
+            let (field, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let value = value.trim();
+
+            match field {
+                "rom" => {
+                    rom_hash = Some(
+                        parse_hash(value)
+                            .ok_or_else(|| error(format!("invalid hash `{}`", value)))?,
+                    )
+                }
+                "seed" => {
+                    seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| error(format!("invalid seed `{}`", value)))?,
+                    )
+                }
+                "quirks" => quirks = Some(parse_quirks(value).map_err(error)?),
+                "ipf" => {
+                    instructions_per_frame = value
+                        .parse()
+                        .ok()
+                        .filter(|ipf| *ipf > 0)
+                        .ok_or_else(|| error(format!("invalid ipf `{}`", value)))?
+                }
+                "frames" => {
+                    frames = Some(
+                        value
+                            .parse()
+                            .map_err(|_| error(format!("invalid frame count `{}`", value)))?,
+                    )
+                }
+                _ => {
+                    let (frame, held) = headless::parse_event(line).map_err(error)?;
+                    keys.push(frame, held);
+                }
+            }
+        }
+
+        Ok(Self {
+            rom_hash: rom_hash.ok_or("missing `rom` line")?,
+            seed: seed.ok_or("missing `seed` line")?,
+            quirks: quirks.ok_or("missing `quirks` line")?,
+            instructions_per_frame,
+            frames: frames.ok_or("missing `frames` line")?,
+            keys,
+        })
+    }
+}
+
+impl fmt::Display for Movie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "chip8-movie {}", MOVIE_VERSION)?;
+        writeln!(f, "rom {}", hex(&self.rom_hash))?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "quirks {}", quirks_to_string(&self.quirks))?;
+        writeln!(f, "ipf {}", self.instructions_per_frame)?;
+        writeln!(f, "frames {}", self.frames)?;
+
+        for (frame, keys) in self.keys.events() {
+            writeln!(f, "{} {}", frame, headless::keys_to_string(*keys))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn parse_hash(text: &str) -> Option<[u8; 20]> {
+    if text.len() != 40 || !text.is_ascii() {
+        return None;
+    }
+
+    let mut hash = [0; 20];
+    for (index, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[index * 2..index * 2 + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+/// Writes a preset name, or `key=value` pairs for custom quirks.
+fn quirks_to_string(quirks: &Quirks) -> String {
+    if let Some(name) = quirks.name() {
+        return name.to_string();
+    }
+
+    let index_increment = match quirks.index_increment {
+        IndexIncrement::XPlusOne => "x+1",
+        IndexIncrement::X => "x",
+        IndexIncrement::Unchanged => "none",
+    };
+    format!(
+        "vf_reset={} index_increment={} display_wait={} clipping={} \
+         shifting={} jumping={} extended_memory={}",
+        quirks.vf_reset as u8,
+        index_increment,
+        quirks.display_wait as u8,
+        quirks.clipping as u8,
+        quirks.shifting as u8,
+        quirks.jumping as u8,
+        quirks.extended_memory as u8
+    )
+}
+
+fn parse_quirks(text: &str) -> Result<Quirks, String> {
+    if let Some(quirks) = Quirks::from_name(text) {
+        return Ok(quirks);
+    }
+
+    let mut quirks = Quirks::default();
+    for pair in text.split_whitespace() {
+        let (name, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("unknown quirk preset `{}`", pair))?;
+        let invalid = || format!("invalid value `{}` for `{}`", value, name);
+        let flag = || match value {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            _ => Err(invalid()),
+        };
+
+        match name {
+            "vf_reset" => quirks.vf_reset = flag()?,
+            "display_wait" => quirks.display_wait = flag()?,
+            "clipping" => quirks.clipping = flag()?,
+            "shifting" => quirks.shifting = flag()?,
+            "jumping" => quirks.jumping = flag()?,
+            "extended_memory" => quirks.extended_memory = flag()?,
+            "index_increment" => {
+                quirks.index_increment = match value {
+                    "x+1" => IndexIncrement::XPlusOne,
+                    "x" => IndexIncrement::X,
+                    "none" => IndexIncrement::Unchanged,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(format!("unknown quirk `{}`", name)),
+        }
+    }
+
+    Ok(quirks)
+}
@@ -0,0 +1,146 @@
+//! Display colours.
+
+/// An RGB colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Parses `RRGGBB`, optionally prefixed with `#`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let digits = text.strip_prefix('#').unwrap_or(text);
+        if digits.len() != 6 || !digits.is_ascii() {
+            return None;
+        }
+
+        let channel = |index: usize| u8::from_str_radix(&digits[index..index + 2], 16).ok();
+        Some(Self::rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+/// Colours for the four pixel values: off, plane 1, plane 2 and both
+/// planes. Programs that only use one plane only show the first two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [Color; 4],
+}
+
+impl Palette {
+    /// White on black, with grays for the XO-CHIP planes.
+    pub const CLASSIC: Self = Self {
+        colors: [
+            Color::rgb(0, 0, 0),
+            Color::rgb(255, 255, 255),
+            Color::rgb(170, 170, 170),
+            Color::rgb(85, 85, 85),
+        ],
+    };
+
+    /// Amber phosphor monitor.
+    pub const AMBER: Self = Self {
+        colors: [
+            Color::rgb(0x1A, 0x0F, 0x00),
+            Color::rgb(0xFF, 0xB0, 0x00),
+            Color::rgb(0xAA, 0x75, 0x00),
+            Color::rgb(0x5C, 0x3F, 0x00),
+        ],
+    };
+
+    /// Green phosphor monitor.
+    pub const GREEN: Self = Self {
+        colors: [
+            Color::rgb(0x00, 0x14, 0x00),
+            Color::rgb(0x33, 0xFF, 0x33),
+            Color::rgb(0x22, 0xAA, 0x22),
+            Color::rgb(0x11, 0x55, 0x11),
+        ],
+    };
+
+    /// Dark pixels on a greenish LCD.
+    pub const LCD: Self = Self {
+        colors: [
+            Color::rgb(0x9B, 0xBC, 0x0F),
+            Color::rgb(0x0F, 0x38, 0x0F),
+            Color::rgb(0x30, 0x62, 0x30),
+            Color::rgb(0x56, 0x7A, 0x1A),
+        ],
+    };
+
+    /// The default colours of the Octo IDE.
+    pub const OCTO: Self = Self {
+        colors: [
+            Color::rgb(0x99, 0x66, 0x00),
+            Color::rgb(0xFF, 0xCC, 0x00),
+            Color::rgb(0xFF, 0x66, 0x00),
+            Color::rgb(0x66, 0x22, 0x00),
+        ],
+    };
+
+    /// The built-in themes, in the same order as [`Self::THEME_NAMES`].
+    pub const THEMES: [Self; 5] = [
+        Self::CLASSIC,
+        Self::AMBER,
+        Self::GREEN,
+        Self::LCD,
+        Self::OCTO,
+    ];
+    pub const THEME_NAMES: [&'static str; 5] = ["classic", "amber", "green", "lcd", "octo"];
+
+    pub fn new() -> Self {
+        Self::CLASSIC
+    }
+
+    /// The built-in theme called `name`, one of [`Self::THEME_NAMES`].
+    pub fn theme(name: &str) -> Option<Self> {
+        let index = Self::THEME_NAMES.iter().position(|theme| *theme == name)?;
+        Some(Self::THEMES[index])
+    }
+
+    /// Parses a theme name or a comma-separated list of colours. Two colours
+    /// set the background and foreground and keep the classic plane colours;
+    /// four set every pixel value.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Some(theme) = Self::theme(text) {
+            return Ok(theme);
+        }
+        if !text.contains(',') {
+            return Err(format!(
+                "unknown theme `{}`, expected one of {} or a list of colours",
+                text,
+                Self::THEME_NAMES.join(", ")
+            ));
+        }
+
+        let colors = text
+            .split(',')
+            .map(|color| {
+                Color::parse(color.trim()).ok_or_else(|| format!("invalid colour `{}`", color))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if colors.len() != 2 && colors.len() != 4 {
+            return Err(format!("expected 2 or 4 colours, got {}", colors.len()));
+        }
+
+        let mut palette = Self::CLASSIC;
+        palette.colors[..colors.len()].copy_from_slice(&colors);
+        Ok(palette)
+    }
+
+    /// The colour for a pixel value from [`crate::Machine::display`].
+    pub fn color(&self, pixel: u8) -> Color {
+        self.colors[pixel as usize & 3]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,30 +1,125 @@
+use crate::error::ExecError;
 use crate::ram::Ram;
+use crate::state::{StateError, StateReader, StateWriter};
 
 pub const SCREEN_WIDTH: usize = 64;
 pub const SCREEN_HEIGHT: usize = 32;
-const MASK_MSBIT: u8 = 0b1000_0000;
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
 
+/// The display: 64x32, or 128x64 in SUPER-CHIP hi-res mode.
+///
+/// The display buffer is always allocated at the hi-res size; only the top
+/// left [`Ppu::width`] x [`Ppu::height`] pixels are in use. Each pixel holds
+/// one bit per XO-CHIP bitplane, so its value is a colour index from 0 to 3;
+/// programs that never select the second plane only use 0 and 1.
 pub struct Ppu {
-    pub display: [[u8; SCREEN_WIDTH]; SCREEN_HEIGHT],
+    pub display: [[u8; HIRES_WIDTH]; HIRES_HEIGHT],
     pub pixel_flipped: bool,
+    hires: bool,
+    planes: u8,
 }
 
 impl Ppu {
     pub fn new() -> Self {
         Self {
-            display: [[0; SCREEN_WIDTH]; SCREEN_HEIGHT],
+            display: [[0; HIRES_WIDTH]; HIRES_HEIGHT],
             pixel_flipped: false,
+            hires: false,
+            planes: 0b01,
         }
     }
 
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_WIDTH
+        } else {
+            SCREEN_WIDTH
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_HEIGHT
+        } else {
+            SCREEN_HEIGHT
+        }
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    /// Switches between 64x32 and 128x64 mode, clearing all planes.
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        for row in self.display.iter_mut() {
+            row.fill(0);
+        }
+    }
+
+    /// The bitplanes affected by drawing, clearing and scrolling, as a mask.
+    pub fn planes(&self) -> u8 {
+        self.planes
+    }
+
+    pub fn select_planes(&mut self, planes: u8) {
+        self.planes = planes & 0b11;
+    }
+
+    /// Clears the selected planes.
     pub fn clear(&mut self) {
-        for height in 0..SCREEN_HEIGHT {
-            for width in 0..SCREEN_WIDTH {
-                self.display[height][width] = 0;
+        for row in self.display.iter_mut() {
+            for pixel in row.iter_mut() {
+                *pixel &= !self.planes;
+            }
+        }
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll(0, rows as isize);
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll(0, -(rows as isize));
+    }
+
+    pub fn scroll_right(&mut self, columns: usize) {
+        self.scroll(columns as isize, 0);
+    }
+
+    pub fn scroll_left(&mut self, columns: usize) {
+        self.scroll(-(columns as isize), 0);
+    }
+
+    /// Moves the selected planes by the given offset, filling with blank pixels.
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let previous = self.display;
+        let (width, height) = (self.width() as isize, self.height() as isize);
+
+        for y in 0..height {
+            for x in 0..width {
+                let (source_x, source_y) = (x - dx, y - dy);
+                let source = if (0..width).contains(&source_x) && (0..height).contains(&source_y) {
+                    previous[source_y as usize][source_x as usize]
+                } else {
+                    0
+                };
+
+                let pixel = &mut self.display[y as usize][x as usize];
+                *pixel = (*pixel & !self.planes) | (source & self.planes);
             }
         }
     }
 
+    /// Draws a sprite by XORing it onto each selected plane. The start
+    /// position wraps around the screen; pixels past the edges are dropped
+    /// when `clip` is set and wrap around otherwise.
+    ///
+    /// Sprites are 8 pixels wide with one byte per row, or 16 pixels wide with
+    /// two bytes per row. With both planes selected, the sprite data for the
+    /// second plane follows the data for the first.
+    #[allow(clippy::too_many_arguments)]
     pub fn render_pixels(
         &mut self,
         vx: u8,
@@ -32,39 +127,97 @@ impl Ppu {
         i: u16,
         sprite_height: u8,
         sprite_width: u8,
+        clip: bool,
         ram: &mut Ram,
-    ) {
+    ) -> Result<(), ExecError> {
         self.pixel_flipped = false;
-        let mut y = vy as usize % SCREEN_HEIGHT;
-
-        for height in 0..sprite_height {
-            let mut byte = ram.read_byte(i + height as u16);
-            let mut x = vx as usize % SCREEN_WIDTH;
+        let (width, height) = (self.width(), self.height());
+        let start_x = vx as usize % width;
+        let start_y = vy as usize % height;
+        let bytes_per_row = sprite_width as u16 / 8;
+        let mut address = i;
 
-            if y >= SCREEN_HEIGHT {
-                break;
+        for plane in [0b01, 0b10] {
+            if self.planes & plane == 0 {
+                continue;
             }
 
-            for _ in 0..sprite_width {
-                if x >= SCREEN_WIDTH {
-                    continue;
+            for row in 0..sprite_height as usize {
+                let mut bits = 0u16;
+                for _ in 0..bytes_per_row {
+                    bits = bits << 8 | ram.read_byte(address)? as u16;
+                    address = address.wrapping_add(1);
                 }
 
-                let bit = (byte & MASK_MSBIT) >> 7;
+                let mut y = start_y + row;
+                if y >= height {
+                    if clip {
+                        continue;
+                    }
+                    y %= height;
+                }
+
+                for column in 0..sprite_width as usize {
+                    let bit = (bits >> (sprite_width as usize - 1 - column)) & 1;
 
-                if bit == 1 {
-                    if self.display[y][x] == 1 {
-                        self.pixel_flipped = true;
-                        self.display[y][x] = 0
-                    } else {
-                        self.display[y][x] = 1
+                    let mut x = start_x + column;
+                    if x >= width {
+                        if clip {
+                            break;
+                        }
+                        x %= width;
+                    }
+
+                    if bit == 1 {
+                        if self.display[y][x] & plane != 0 {
+                            self.pixel_flipped = true;
+                        }
+                        self.display[y][x] ^= plane;
                     }
                 }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn save(&self, writer: &mut StateWriter) {
+        for row in &self.display {
+            writer.bytes(row);
+        }
+        writer.bool(self.pixel_flipped);
+        writer.bool(self.hires);
+        writer.u8(self.planes);
+    }
 
-                x += 1;
-                byte <<= 1;
+    pub(crate) fn load(reader: &mut StateReader) -> Result<Self, StateError> {
+        let mut display = [[0; HIRES_WIDTH]; HIRES_HEIGHT];
+        for row in display.iter_mut() {
+            *row = reader.array()?;
+            // Pixels hold one bit per plane
+            if row.iter().any(|pixel| *pixel > 0b11) {
+                return Err(StateError::Corrupt);
             }
-            y += 1;
         }
+
+        let pixel_flipped = reader.bool()?;
+        let hires = reader.bool()?;
+        let planes = reader.u8()?;
+        if planes > 0b11 {
+            return Err(StateError::Corrupt);
+        }
+
+        Ok(Self {
+            display,
+            pixel_flipped,
+            hires,
+            planes,
+        })
+    }
+}
+
+impl Default for Ppu {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -10,11 +10,11 @@ impl ProgramCounter {
     }
 
     pub fn next(&mut self) {
-        self.value += 2;
+        self.value = self.value.wrapping_add(2);
     }
 
     pub fn skip_next(&mut self) {
-        self.value += 4;
+        self.value = self.value.wrapping_add(4);
     }
 
     pub fn set_value(&mut self, value: u16) {
@@ -25,3 +25,9 @@ impl ProgramCounter {
         self.value
     }
 }
+
+impl Default for ProgramCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,137 @@
+use crate::state::{StateError, StateReader, StateWriter};
+
+/// How `FX55` and `FX65` leave the I register after the transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexIncrement {
+    /// I is advanced past the last register, to `I + X + 1` (COSMAC VIP).
+    XPlusOne,
+    /// I is advanced to `I + X` (CHIP-48).
+    X,
+    /// I is left unchanged (SUPER-CHIP).
+    Unchanged,
+}
+
+/// Behaviour that differs between CHIP-8 interpreters.
+///
+/// The presets match the platforms selectable in the `5-quirks.ch8` test ROM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8XY1`, `8XY2` and `8XY3` reset VF to 0.
+    pub vf_reset: bool,
+    /// Where `FX55`/`FX65` leave I.
+    pub index_increment: IndexIncrement,
+    /// `DXYN` waits for the next frame before execution continues, limiting
+    /// drawing to one sprite per frame.
+    pub display_wait: bool,
+    /// Sprites are clipped at the screen edges instead of wrapping around.
+    pub clipping: bool,
+    /// `8XY6`/`8XYE` shift VX in place instead of shifting VY into VX.
+    pub shifting: bool,
+    /// `BNNN` jumps to `XNN + VX` instead of `NNN + V0`.
+    pub jumping: bool,
+    /// Memory spans the full 64 KB XO-CHIP address space instead of 4 KB.
+    pub extended_memory: bool,
+}
+
+impl Quirks {
+    pub const COSMAC_VIP: Self = Self {
+        vf_reset: true,
+        index_increment: IndexIncrement::XPlusOne,
+        display_wait: true,
+        clipping: true,
+        shifting: false,
+        jumping: false,
+        extended_memory: false,
+    };
+
+    pub const CHIP_48: Self = Self {
+        vf_reset: false,
+        index_increment: IndexIncrement::X,
+        display_wait: false,
+        clipping: true,
+        shifting: true,
+        jumping: true,
+        extended_memory: false,
+    };
+
+    pub const SUPER_CHIP: Self = Self {
+        vf_reset: false,
+        index_increment: IndexIncrement::Unchanged,
+        display_wait: false,
+        clipping: true,
+        shifting: true,
+        jumping: true,
+        extended_memory: false,
+    };
+
+    pub const XO_CHIP: Self = Self {
+        vf_reset: false,
+        index_increment: IndexIncrement::XPlusOne,
+        display_wait: false,
+        clipping: false,
+        shifting: false,
+        jumping: false,
+        extended_memory: true,
+    };
+
+    /// Names accepted by [`Quirks::from_name`].
+    pub const PRESET_NAMES: [&'static str; 4] = ["cosmac-vip", "chip-48", "schip", "xo-chip"];
+
+    /// Looks up a preset by name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "cosmac-vip" | "vip" | "chip-8" | "chip8" => Some(Self::COSMAC_VIP),
+            "chip-48" | "chip48" => Some(Self::CHIP_48),
+            "schip" | "super-chip" | "superchip" => Some(Self::SUPER_CHIP),
+            "xo-chip" | "xochip" => Some(Self::XO_CHIP),
+            _ => None,
+        }
+    }
+
+    /// The canonical name of the preset these quirks match, if any.
+    pub fn name(&self) -> Option<&'static str> {
+        Self::PRESET_NAMES
+            .into_iter()
+            .find(|name| Self::from_name(name) == Some(*self))
+    }
+
+    pub(crate) fn save(&self, writer: &mut StateWriter) {
+        writer.bool(self.vf_reset);
+        writer.u8(match self.index_increment {
+            IndexIncrement::XPlusOne => 0,
+            IndexIncrement::X => 1,
+            IndexIncrement::Unchanged => 2,
+        });
+        writer.bool(self.display_wait);
+        writer.bool(self.clipping);
+        writer.bool(self.shifting);
+        writer.bool(self.jumping);
+        writer.bool(self.extended_memory);
+    }
+
+    pub(crate) fn load(reader: &mut StateReader) -> Result<Self, StateError> {
+        let vf_reset = reader.bool()?;
+        let index_increment = match reader.u8()? {
+            0 => IndexIncrement::XPlusOne,
+            1 => IndexIncrement::X,
+            2 => IndexIncrement::Unchanged,
+            _ => return Err(StateError::Corrupt),
+        };
+
+        Ok(Self {
+            vf_reset,
+            index_increment,
+            display_wait: reader.bool()?,
+            clipping: reader.bool()?,
+            shifting: reader.bool()?,
+            jumping: reader.bool()?,
+            extended_memory: reader.bool()?,
+        })
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::COSMAC_VIP
+    }
+}
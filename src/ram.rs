@@ -1,10 +1,28 @@
+use crate::error::ExecError;
+use crate::state::{StateError, StateReader, StateWriter};
+
+/// Size of the address space in bytes.
+pub const RAM_SIZE: usize = 4096;
+
+/// Size of the XO-CHIP address space in bytes.
+pub const EXTENDED_RAM_SIZE: usize = 65536;
+
+/// Address of the SUPER-CHIP 8x10 hex font used by `FX30`.
+pub const BIG_FONT_ADDRESS: u16 = 0x050;
+
+/// Memory with the hex font sprites stored at 0x000: 4 KB, or 64 KB for
+/// XO-CHIP.
 pub struct Ram {
-    data: [u8; 4096],
+    data: Vec<u8>,
 }
 
 impl Ram {
     pub fn new() -> Self {
-        let mut data = [0; 4096];
+        Self::with_size(RAM_SIZE)
+    }
+
+    pub fn with_size(size: usize) -> Self {
+        let mut data = vec![0; size];
 
         // Programs may also refer to a group of sprites representing the hexadecimal
         // digits 0 through F. These sprites are 5 bytes long, or 8x5 pixels.
@@ -32,18 +50,84 @@ impl Ram {
             data[index] = *value;
         }
 
+        // SUPER-CHIP adds larger 8x10 digits, stored right after the small ones.
+        let big_sprites: [[u8; 10]; 16] = [
+            [0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF],
+            [0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF],
+            [0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF],
+            [0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF],
+            [0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03],
+            [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF],
+            [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF],
+            [0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18],
+            [0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF],
+            [0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF],
+            [0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3],
+            [0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC],
+            [0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C],
+            [0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC],
+            [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF],
+            [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0],
+        ];
+
+        for (index, value) in big_sprites.iter().flatten().enumerate() {
+            data[BIG_FONT_ADDRESS as usize + index] = *value;
+        }
+
         Self { data }
     }
 
-    pub fn write_byte(&mut self, address: u16, value: u8) {
-        self.data[address as usize] = value;
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Grows or shrinks the address space, keeping the contents that fit.
+    pub fn resize(&mut self, size: usize) {
+        self.data.resize(size, 0);
     }
 
-    pub fn read_byte(&self, address: u16) -> u8 {
-        self.data[address as usize]
+    pub fn write_byte(&mut self, address: u16, value: u8) -> Result<(), ExecError> {
+        let byte = self
+            .data
+            .get_mut(address as usize)
+            .ok_or(ExecError::MemoryOutOfBounds { address })?;
+        *byte = value;
+        Ok(())
     }
 
-    pub fn get_instruction(&self, address: u16) -> u16 {
-        (self.read_byte(address) as u16) << 8 | self.read_byte(address + 1) as u16
+    pub fn read_byte(&self, address: u16) -> Result<u8, ExecError> {
+        self.data
+            .get(address as usize)
+            .copied()
+            .ok_or(ExecError::MemoryOutOfBounds { address })
+    }
+
+    pub fn get_instruction(&self, address: u16) -> Result<u16, ExecError> {
+        if address as usize + 1 >= self.data.len() {
+            return Err(ExecError::PcOutOfBounds { address });
+        }
+        Ok((self.data[address as usize] as u16) << 8 | self.data[address as usize + 1] as u16)
+    }
+
+    pub(crate) fn save(&self, writer: &mut StateWriter) {
+        writer.u32(self.data.len() as u32);
+        writer.bytes(&self.data);
+    }
+
+    pub(crate) fn load(reader: &mut StateReader) -> Result<Self, StateError> {
+        let size = reader.u32()? as usize;
+        if size != RAM_SIZE && size != EXTENDED_RAM_SIZE {
+            return Err(StateError::Corrupt);
+        }
+
+        Ok(Self {
+            data: reader.bytes(size)?.to_vec(),
+        })
+    }
+}
+
+impl Default for Ram {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -1,7 +1,16 @@
+use crate::error::ExecError;
+use crate::state::{StateError, StateReader, StateWriter};
+
+/// Maximum depth of the call stack.
+pub const STACK_SIZE: usize = 16;
+
+/// The V0-VF general purpose registers, the I register, the call stack and
+/// the SUPER-CHIP RPL user flags.
 pub struct Registers {
     vn: [u8; 16],
     i: u16,
-    stack: Vec::<u16>,
+    stack: Vec<u16>,
+    rpl_flags: [u8; 16],
 }
 
 impl Registers {
@@ -9,7 +18,8 @@ impl Registers {
         Self {
             vn: [0; 16],
             i: 0,
-            stack: Vec::new(),
+            stack: Vec::with_capacity(STACK_SIZE),
+            rpl_flags: [0; 16],
         }
     }
 
@@ -28,12 +38,66 @@ impl Registers {
     pub fn set_i(&mut self, value: u16) {
         self.i = value;
     }
-    
-    pub fn stack_pop(&mut self) -> u16 {
-        self.stack.pop().unwrap()
+
+    /// The SUPER-CHIP RPL user flags used by `FX75`/`FX85`.
+    pub fn get_rpl_flag(&self, index: u8) -> u8 {
+        self.rpl_flags[index as usize]
+    }
+
+    pub fn set_rpl_flag(&mut self, index: u8, value: u8) {
+        self.rpl_flags[index as usize] = value;
     }
 
-    pub fn stack_push(&mut self, value: u16) {
+    pub fn stack(&self) -> &[u16] {
+        &self.stack
+    }
+
+    pub fn stack_pop(&mut self) -> Result<u16, ExecError> {
+        self.stack.pop().ok_or(ExecError::StackUnderflow)
+    }
+
+    pub fn stack_push(&mut self, value: u16) -> Result<(), ExecError> {
+        if self.stack.len() >= STACK_SIZE {
+            return Err(ExecError::StackOverflow);
+        }
         self.stack.push(value);
+        Ok(())
+    }
+
+    pub(crate) fn save(&self, writer: &mut StateWriter) {
+        writer.bytes(&self.vn);
+        writer.u16(self.i);
+        writer.u8(self.stack.len() as u8);
+        for address in &self.stack {
+            writer.u16(*address);
+        }
+        writer.bytes(&self.rpl_flags);
+    }
+
+    pub(crate) fn load(reader: &mut StateReader) -> Result<Self, StateError> {
+        let vn = reader.array()?;
+        let i = reader.u16()?;
+        let depth = reader.u8()? as usize;
+        if depth > STACK_SIZE {
+            return Err(StateError::Corrupt);
+        }
+        let mut stack = Vec::with_capacity(STACK_SIZE);
+        for _ in 0..depth {
+            stack.push(reader.u16()?);
+        }
+        let rpl_flags = reader.array()?;
+
+        Ok(Self {
+            vn,
+            i,
+            stack,
+            rpl_flags,
+        })
+    }
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -0,0 +1,157 @@
+//! A rolling history of save states for playing a game backwards.
+//!
+//! Only the newest state is kept in full. Every older frame is stored as the
+//! difference to the frame after it: the two states XORed together, with the
+//! runs of zero bytes (everything that did not change) run-length encoded.
+//! Most frames only touch a few bytes of memory and display, so a frame
+//! usually costs a few dozen bytes instead of a full state.
+
+use std::collections::VecDeque;
+
+/// Default history length: 30 seconds at 60 frames per second.
+pub const DEFAULT_REWIND_FRAMES: usize = 30 * 60;
+
+/// Save states for the most recent frames, from
+/// [`Machine::save_state`](crate::Machine::save_state).
+pub struct RewindBuffer {
+    capacity: usize,
+    latest: Vec<u8>,
+    /// `deltas[n]` turns the state after it back into the one before it;
+    /// the newest delta is at the back.
+    deltas: VecDeque<Vec<u8>>,
+}
+
+impl RewindBuffer {
+    /// Creates a buffer holding up to `frames` states.
+    pub fn new(frames: usize) -> Self {
+        Self {
+            capacity: frames.max(1),
+            latest: Vec::new(),
+            deltas: VecDeque::new(),
+        }
+    }
+
+    /// Number of states held, including the newest.
+    pub fn len(&self) -> usize {
+        if self.latest.is_empty() {
+            0
+        } else {
+            self.deltas.len() + 1
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes used by the stored states.
+    pub fn memory_used(&self) -> usize {
+        self.latest.len() + self.deltas.iter().map(Vec::len).sum::<usize>()
+    }
+
+    pub fn clear(&mut self) {
+        self.latest.clear();
+        self.deltas.clear();
+    }
+
+    /// Records the state of a new frame, dropping the oldest frame when full.
+    pub fn push(&mut self, state: Vec<u8>) {
+        // States of different sizes (memory was resized) cannot be diffed
+        if state.len() != self.latest.len() {
+            self.deltas.clear();
+        } else {
+            self.deltas.push_back(encode(&self.latest, &state));
+            if self.deltas.len() >= self.capacity {
+                self.deltas.pop_front();
+            }
+        }
+        self.latest = state;
+    }
+
+    /// Drops the newest state and returns the one before it, or `None` once
+    /// the oldest state is reached.
+    pub fn step_back(&mut self) -> Option<&[u8]> {
+        let delta = self.deltas.pop_back()?;
+        apply(&mut self.latest, &delta);
+        Some(&self.latest)
+    }
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_REWIND_FRAMES)
+    }
+}
+
+/// Encodes `old ^ new` as alternating runs: a varint count of unchanged
+/// bytes, a varint count of changed bytes, then the changed bytes XORed.
+fn encode(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut delta = Vec::new();
+    let mut position = 0;
+
+    while position < old.len() {
+        let unchanged = old[position..]
+            .iter()
+            .zip(&new[position..])
+            .take_while(|(old, new)| old == new)
+            .count();
+        position += unchanged;
+        if position == old.len() {
+            break;
+        }
+
+        let changed = old[position..]
+            .iter()
+            .zip(&new[position..])
+            .take_while(|(old, new)| old != new)
+            .count();
+        write_varint(&mut delta, unchanged);
+        write_varint(&mut delta, changed);
+        delta.extend(
+            old[position..position + changed]
+                .iter()
+                .zip(&new[position..position + changed])
+                .map(|(old, new)| old ^ new),
+        );
+        position += changed;
+    }
+
+    delta
+}
+
+fn apply(state: &mut [u8], delta: &[u8]) {
+    let mut position = 0;
+    let mut input = delta;
+
+    while !input.is_empty() {
+        position += read_varint(&mut input);
+        let changed = read_varint(&mut input);
+        for (byte, xor) in state[position..position + changed].iter_mut().zip(input) {
+            *byte ^= xor;
+        }
+        input = &input[changed..];
+        position += changed;
+    }
+}
+
+fn write_varint(output: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    while let Some((&byte, rest)) = input.split_first() {
+        *input = rest;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    value
+}
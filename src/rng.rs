@@ -0,0 +1,100 @@
+use crate::state::{StateError, StateReader, StateWriter};
+
+/// Random number source for `CXNN`.
+///
+/// A seeded generator always produces the same bytes for the same seed, so
+/// runs with equal ROM, input and seed are reproducible. Tests can replace it
+/// with a fixed byte sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    kind: Kind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    Seeded { state: u64 },
+    Sequence { bytes: Vec<u8>, position: usize },
+}
+
+impl Rng {
+    /// A SplitMix64 generator starting from `seed`.
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            kind: Kind::Seeded { state: seed },
+        }
+    }
+
+    /// A generator seeded from the operating system.
+    pub fn from_entropy() -> Self {
+        Self::from_seed(rand::random())
+    }
+
+    /// Returns `bytes` in order, starting over after the last one. An empty
+    /// sequence always returns 0.
+    pub fn sequence(bytes: Vec<u8>) -> Self {
+        Self {
+            kind: Kind::Sequence { bytes, position: 0 },
+        }
+    }
+
+    pub fn next_byte(&mut self) -> u8 {
+        match &mut self.kind {
+            Kind::Seeded { state } => {
+                *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                let mut z = *state;
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                ((z ^ (z >> 31)) >> 56) as u8
+            }
+            Kind::Sequence { bytes, position } => {
+                let Some(byte) = bytes.get(*position).copied() else {
+                    return 0;
+                };
+                *position = (*position + 1) % bytes.len();
+                byte
+            }
+        }
+    }
+
+    pub(crate) fn save(&self, writer: &mut StateWriter) {
+        match &self.kind {
+            Kind::Seeded { state } => {
+                writer.u8(0);
+                writer.u64(*state);
+            }
+            Kind::Sequence { bytes, position } => {
+                writer.u8(1);
+                writer.u32(bytes.len() as u32);
+                writer.bytes(bytes);
+                writer.u32(*position as u32);
+            }
+        }
+    }
+
+    pub(crate) fn load(reader: &mut StateReader) -> Result<Self, StateError> {
+        let kind = match reader.u8()? {
+            0 => Kind::Seeded {
+                state: reader.u64()?,
+            },
+            1 => {
+                let length = reader.u32()? as usize;
+                let bytes = reader.bytes(length)?.to_vec();
+                let position = reader.u32()? as usize;
+                // An empty sequence stays at 0, any other must point into it
+                if position >= bytes.len().max(1) {
+                    return Err(StateError::Corrupt);
+                }
+                Kind::Sequence { bytes, position }
+            }
+            _ => return Err(StateError::Corrupt),
+        };
+
+        Ok(Self { kind })
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
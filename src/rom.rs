@@ -1,6 +1,7 @@
 use std::fs::File;
 use std::io::{Error, Read};
 
+/// A program image read from disk.
 pub struct Rom {
     pub data: Vec<u8>,
 }
@@ -13,4 +14,9 @@ impl Rom {
 
         Ok(Self { data })
     }
+
+    /// SHA-1 digest of the program, identifying it regardless of file name.
+    pub fn sha1(&self) -> [u8; 20] {
+        sha1_smol::Sha1::from(&self.data).digest().bytes()
+    }
 }
@@ -0,0 +1,274 @@
+//! Per-ROM settings, looked up by the SHA-1 of the ROM data.
+//!
+//! ```toml
+//! [b232ef880bd6060fb45fa6effed7edf0ae95670e]
+//! title = "Pong"
+//! platform = "chip-8"            # chip-8, schip or xo-chip
+//! quirks = { vf_reset = false }  # adjusts the platform's quirks
+//! ipf = 10
+//! palette = "classic"
+//! keyboard = { layout = "cosmac", keys = { Up = "1", Down = "4" } }
+//! ```
+//!
+//! Every field is optional. `keyboard` may also be just a layout name.
+//! [`RomDatabase::bundled`] covers the ROMs in `roms/`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use toml::{Table, Value};
+
+use crate::config::{as_table, bind_all, check_settings};
+use crate::keymap::KeyMap;
+use crate::movie::parse_hash;
+use crate::palette::Palette;
+use crate::quirks::{IndexIncrement, Quirks};
+
+/// File the frontends read from the working directory, if present, to add
+/// to or override the bundled database.
+pub const USER_DATABASE_FILE: &str = "chip8-roms.toml";
+
+const BUNDLED: &str = include_str!("../data/roms.toml");
+
+/// The interpreter a ROM was written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl Platform {
+    pub const NAMES: [&'static str; 3] = ["chip-8", "schip", "xo-chip"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "chip-8" => Some(Self::Chip8),
+            "schip" => Some(Self::SuperChip),
+            "xo-chip" => Some(Self::XoChip),
+            _ => None,
+        }
+    }
+
+    /// The quirk preset matching the platform.
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Self::Chip8 => Quirks::COSMAC_VIP,
+            Self::SuperChip => Quirks::SUPER_CHIP,
+            Self::XoChip => Quirks::XO_CHIP,
+        }
+    }
+}
+
+/// Settings for one ROM.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RomEntry {
+    pub title: Option<String>,
+    pub platform: Option<Platform>,
+    /// The platform's quirks with any adjustments applied.
+    pub quirks: Option<Quirks>,
+    pub instructions_per_frame: Option<usize>,
+    pub palette: Option<Palette>,
+    /// Keyboard layout replacing the user's.
+    pub layout: Option<KeyMap>,
+    /// Bindings added on top of the layout.
+    pub keys: Vec<(String, u8)>,
+}
+
+impl RomEntry {
+    /// The key map to use for this ROM when the user's is `base`.
+    pub fn keymap(&self, base: &KeyMap) -> KeyMap {
+        let mut keymap = self.layout.clone().unwrap_or_else(|| base.clone());
+        for (name, key) in &self.keys {
+            keymap.bind(name, *key);
+        }
+        keymap
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RomDatabase {
+    entries: HashMap<[u8; 20], RomEntry>,
+}
+
+impl RomDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The database for the ROMs shipped in `roms/`.
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED).expect("bundled ROM database is invalid")
+    }
+
+    /// The bundled database, extended with [`USER_DATABASE_FILE`] from the
+    /// working directory if it exists.
+    pub fn load_default() -> Result<Self, String> {
+        let mut database = Self::bundled();
+        let path = Path::new(USER_DATABASE_FILE);
+        if path.exists() {
+            let user = Self::load(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            database.extend(user);
+        }
+        Ok(database)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::parse(&source)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let table: Table = source
+            .parse()
+            .map_err(|err: toml::de::Error| err.message().to_string())?;
+        let mut database = Self::new();
+
+        for (hash, value) in &table {
+            let sha1 = parse_hash(hash).ok_or_else(|| format!("`{}` is not a SHA-1", hash))?;
+            database.entries.insert(sha1, parse_entry(value, hash)?);
+        }
+
+        Ok(database)
+    }
+
+    pub fn get(&self, sha1: &[u8; 20]) -> Option<&RomEntry> {
+        self.entries.get(sha1)
+    }
+
+    pub fn insert(&mut self, sha1: [u8; 20], entry: RomEntry) {
+        self.entries.insert(sha1, entry);
+    }
+
+    /// Adds the entries of `other`, replacing any for the same ROM.
+    pub fn extend(&mut self, other: RomDatabase) {
+        self.entries.extend(other.entries);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn parse_entry(value: &Value, path: &str) -> Result<RomEntry, String> {
+    let table = as_table(value, path)?;
+    check_settings(
+        table,
+        &["title", "platform", "quirks", "ipf", "palette", "keyboard"],
+        Some(path),
+    )?;
+    let mut entry = RomEntry::default();
+    let string = |name: &str| match table.get(name) {
+        Some(value) => value
+            .as_str()
+            .map(Some)
+            .ok_or_else(|| format!("`{}.{}` must be a string", path, name)),
+        None => Ok(None),
+    };
+
+    entry.title = string("title")?.map(str::to_string);
+
+    if let Some(name) = string("platform")? {
+        let platform = Platform::from_name(name).ok_or_else(|| {
+            format!(
+                "unknown platform `{}`, expected one of {}",
+                name,
+                Platform::NAMES.join(", ")
+            )
+        })?;
+        entry.platform = Some(platform);
+        entry.quirks = Some(platform.quirks());
+    }
+
+    if let Some(quirks) = table.get("quirks") {
+        let base = entry.quirks.unwrap_or_default();
+        entry.quirks = Some(parse_quirks(quirks, base, &format!("{}.quirks", path))?);
+    }
+
+    if let Some(ipf) = table.get("ipf") {
+        let ipf = ipf
+            .as_integer()
+            .filter(|ipf| *ipf > 0)
+            .ok_or_else(|| format!("`{}.ipf` must be a positive number", path))?;
+        entry.instructions_per_frame = Some(ipf as usize);
+    }
+
+    if let Some(palette) = string("palette")? {
+        entry.palette =
+            Some(Palette::parse(palette).map_err(|err| format!("{}.palette: {}", path, err))?);
+    }
+
+    match table.get("keyboard") {
+        Some(Value::String(layout)) => entry.layout = Some(parse_layout(layout)?),
+        Some(keyboard) => {
+            let path = format!("{}.keyboard", path);
+            let keyboard = as_table(keyboard, &path)?;
+            check_settings(keyboard, &["layout", "keys"], Some(&path))?;
+            if let Some(layout) = keyboard.get("layout") {
+                let layout = layout
+                    .as_str()
+                    .ok_or_else(|| format!("`{}.layout` must be a string", path))?;
+                entry.layout = Some(parse_layout(layout)?);
+            }
+            if let Some(keys) = keyboard.get("keys") {
+                let mut bindings = KeyMap::new();
+                bind_all(&mut bindings, keys, &format!("{}.keys", path))?;
+                entry.keys = bindings.bindings().to_vec();
+            }
+        }
+        None => {}
+    }
+
+    Ok(entry)
+}
+
+fn parse_layout(name: &str) -> Result<KeyMap, String> {
+    KeyMap::from_name(name).ok_or_else(|| {
+        format!(
+            "unknown layout `{}`, expected one of {}",
+            name,
+            KeyMap::LAYOUT_NAMES.join(", ")
+        )
+    })
+}
+
+/// Applies a table of quirk flags to `quirks`.
+fn parse_quirks(value: &Value, mut quirks: Quirks, path: &str) -> Result<Quirks, String> {
+    for (name, value) in as_table(value, path)? {
+        let flag = || {
+            value
+                .as_bool()
+                .ok_or_else(|| format!("`{}.{}` must be true or false", path, name))
+        };
+
+        match name.as_str() {
+            "vf_reset" => quirks.vf_reset = flag()?,
+            "display_wait" => quirks.display_wait = flag()?,
+            "clipping" => quirks.clipping = flag()?,
+            "shifting" => quirks.shifting = flag()?,
+            "jumping" => quirks.jumping = flag()?,
+            "extended_memory" => quirks.extended_memory = flag()?,
+            "index_increment" => {
+                quirks.index_increment = match value.as_str() {
+                    Some("x+1") => IndexIncrement::XPlusOne,
+                    Some("x") => IndexIncrement::X,
+                    Some("none") => IndexIncrement::Unchanged,
+                    _ => {
+                        return Err(format!(
+                            "`{}.index_increment` must be \"x+1\", \"x\" or \"none\"",
+                            path
+                        ))
+                    }
+                }
+            }
+            _ => return Err(format!("unknown quirk `{}.{}`", path, name)),
+        }
+    }
+
+    Ok(quirks)
+}
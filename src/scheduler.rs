@@ -0,0 +1,71 @@
+//! Wall-clock pacing for frontends.
+//!
+//! Instead of sleeping a fixed time after each frame, which drifts by however
+//! long emulation and rendering took, the scheduler tracks when the next
+//! frame is due and reports how many frames have come due since the last
+//! call.
+
+use std::time::{Duration, Instant};
+
+/// Frames per second of the CHIP-8 timers.
+pub const FRAME_RATE: u32 = 60;
+
+/// Most frames run at once to catch up. After a longer stall (the window
+/// was dragged, the host was suspended) the backlog is dropped instead.
+pub const MAX_CATCH_UP: u32 = 5;
+
+pub struct FrameScheduler {
+    frame_duration: Duration,
+    speed: f64,
+    next_frame: Instant,
+}
+
+impl FrameScheduler {
+    /// Starts a schedule at 60 frames per second with the first frame due
+    /// at `now`.
+    pub fn new(now: Instant) -> Self {
+        Self {
+            frame_duration: Duration::from_secs(1) / FRAME_RATE,
+            speed: 1.0,
+            next_frame: now,
+        }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Runs frames `speed` times as fast as real time, e.g. 4.0 to
+    /// fast-forward or 0.25 for slow motion.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.01);
+    }
+
+    fn interval(&self) -> Duration {
+        self.frame_duration.div_f64(self.speed)
+    }
+
+    /// Number of frames to run now, at least zero and at most
+    /// [`MAX_CATCH_UP`] times the speed.
+    pub fn frames_due(&mut self, now: Instant) -> u32 {
+        let interval = self.interval();
+        let limit = (MAX_CATCH_UP as f64 * self.speed.max(1.0)) as u32;
+        let mut frames = 0;
+
+        while self.next_frame <= now {
+            if frames == limit {
+                self.next_frame = now + interval;
+                break;
+            }
+            self.next_frame += interval;
+            frames += 1;
+        }
+
+        frames
+    }
+
+    /// When the next frame is due; sleep until then.
+    pub fn next_frame(&self) -> Instant {
+        self.next_frame
+    }
+}
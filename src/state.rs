@@ -0,0 +1,136 @@
+//! Versioned binary save states for [`Machine`](crate::Machine).
+//!
+//! A state starts with the magic bytes `C8ST`, a little-endian `u16` format
+//! version and the SHA-1 of the ROM it was taken from, followed by each
+//! component in a fixed order. Multi-byte values are little-endian.
+
+use std::fmt;
+
+/// Identifies a save state file.
+pub const MAGIC: [u8; 4] = *b"C8ST";
+
+/// Current save state format version. States with a different version are
+/// rejected.
+pub const VERSION: u16 = 3;
+
+/// Why a save state could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// The data does not start with [`MAGIC`].
+    NotAState,
+    /// The state was written by an incompatible format version.
+    UnsupportedVersion(u16),
+    /// The state was taken with a different ROM loaded.
+    RomMismatch,
+    /// The data ends early or holds an impossible value.
+    Corrupt,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAState => write!(f, "not a save state"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "save state version {} is not supported (expected {})",
+                version, VERSION
+            ),
+            Self::RomMismatch => write!(f, "save state belongs to a different ROM"),
+            Self::Corrupt => write!(f, "save state is corrupt"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// Appends values to a save state.
+#[derive(Default)]
+pub(crate) struct StateWriter {
+    data: Vec<u8>,
+}
+
+impl StateWriter {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub(crate) fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub(crate) fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+}
+
+/// Reads values back in the order a [`StateWriter`] wrote them.
+pub(crate) struct StateReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub(crate) fn bytes(&mut self, length: usize) -> Result<&'a [u8], StateError> {
+        if length > self.data.len() {
+            return Err(StateError::Corrupt);
+        }
+        let (bytes, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        Ok(self.bytes(N)?.try_into().expect("length was checked"))
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, StateError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::Corrupt),
+        }
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, StateError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, StateError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+}
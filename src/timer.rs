@@ -1,35 +1,100 @@
-use std::time::Instant;
-
-const TICK_DURATION_MS: u64 = 16;
+use crate::audio::DEFAULT_PITCH;
+use crate::state::{StateError, StateReader, StateWriter};
 
+/// The delay and sound timers, and the XO-CHIP audio pattern and pitch the
+/// buzzer plays while the sound timer runs.
+///
+/// Both count down by one on every [`Timer::tick`], which the machine calls
+/// once per 60 Hz frame of emulated time, so their values depend only on the
+/// number of frames executed and never on the host clock.
 pub struct Timer {
     delay_timer: u8,
-    last_timer_update: Instant,
+    sound_timer: u8,
+    audio_pattern: Option<[u8; 16]>,
+    pitch: u8,
 }
 
 impl Timer {
     pub fn new() -> Self {
         Self {
             delay_timer: 0,
-            last_timer_update: Instant::now(),
+            sound_timer: 0,
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
         }
     }
 
-    pub fn get_delay_timer(&mut self) -> u8 {
-        let elapsed_time = Instant::now().duration_since(self.last_timer_update);
+    pub fn get_delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
 
-        let elapsed_ticks = elapsed_time.as_millis() as u64 / TICK_DURATION_MS;
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay_timer = value;
+    }
 
-        if elapsed_ticks >= self.delay_timer as u64 {
-            0
-        } else {
-            self.delay_timer - elapsed_ticks as u8
+    pub fn get_sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.sound_timer = value;
+    }
+
+    /// The pattern loaded with `F002`, or `None` for the classic tone.
+    pub fn get_audio_pattern(&self) -> Option<[u8; 16]> {
+        self.audio_pattern
+    }
+
+    pub fn set_audio_pattern(&mut self, pattern: [u8; 16]) {
+        self.audio_pattern = Some(pattern);
+    }
+
+    pub fn get_pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    pub fn set_pitch(&mut self, pitch: u8) {
+        self.pitch = pitch;
+    }
+
+    pub fn tick(&mut self) {
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+        self.sound_timer = self.sound_timer.saturating_sub(1);
+    }
+
+    pub(crate) fn save(&self, writer: &mut StateWriter) {
+        writer.u8(self.delay_timer);
+        writer.u8(self.sound_timer);
+        match self.audio_pattern {
+            Some(pattern) => {
+                writer.bool(true);
+                writer.bytes(&pattern);
+            }
+            None => writer.bool(false),
         }
+        writer.u8(self.pitch);
+    }
+
+    pub(crate) fn load(reader: &mut StateReader) -> Result<Self, StateError> {
+        let delay_timer = reader.u8()?;
+        let sound_timer = reader.u8()?;
+        let audio_pattern = if reader.bool()? {
+            Some(reader.array()?)
+        } else {
+            None
+        };
 
+        Ok(Self {
+            delay_timer,
+            sound_timer,
+            audio_pattern,
+            pitch: reader.u8()?,
+        })
     }
+}
 
-    pub fn set_delay_timer(&mut self, value: u8) {
-        self.delay_timer = value;
-        self.last_timer_update = Instant::now();
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -0,0 +1,192 @@
+//! Per-instruction trace log.
+//!
+//! Each executed instruction becomes one line:
+//!
+//! ```text
+//! 00000012 0204 600C LD V0, 0x0C           V0=0C
+//! ```
+//!
+//! holding the cycle number, program counter, opcode, disassembly and the
+//! registers the instruction changed, so two runs can be compared with
+//! `diff`.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+
+use crate::cpu::Cpu;
+use crate::disassembler;
+use crate::ram::Ram;
+use crate::timer::Timer;
+
+/// Selects which instructions are written to the trace.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TraceFilter {
+    /// Only trace instructions at these addresses.
+    pub addresses: Option<RangeInclusive<u16>>,
+    /// Only trace opcode families (the first hex digit of the opcode) whose
+    /// bit is set, e.g. `1 << 0xD` for `DXYN`.
+    pub families: Option<u16>,
+}
+
+impl TraceFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses an inclusive hex address range such as `200-2FF`.
+    pub fn parse_addresses(range: &str) -> Result<RangeInclusive<u16>, String> {
+        let (start, end) = range
+            .split_once('-')
+            .ok_or_else(|| format!("expected `<start>-<end>`, got `{}`", range))?;
+        let parse = |address: &str| {
+            u16::from_str_radix(address.trim_start_matches("0x"), 16)
+                .map_err(|_| format!("invalid address `{}`", address))
+        };
+
+        Ok(parse(start)?..=parse(end)?)
+    }
+
+    /// Parses a comma separated list of opcode families such as `D,8,F`.
+    pub fn parse_families(families: &str) -> Result<u16, String> {
+        families.split(',').try_fold(0, |mask, family| {
+            match u8::from_str_radix(family.trim(), 16) {
+                Ok(digit) if digit < 16 => Ok(mask | 1 << digit),
+                _ => Err(format!("invalid opcode family `{}`", family)),
+            }
+        })
+    }
+
+    fn matches(&self, pc: u16, opcode: u16) -> bool {
+        let in_range = self
+            .addresses
+            .as_ref()
+            .is_none_or(|range| range.contains(&pc));
+        let in_family = self
+            .families
+            .is_none_or(|families| families & (1 << (opcode >> 12)) != 0);
+
+        in_range && in_family
+    }
+}
+
+/// Writes trace records for a [`Machine`](crate::Machine) to a file or any
+/// other writer. Attach one with [`Machine::set_tracer`](crate::Machine::set_tracer).
+pub struct Tracer {
+    writer: Box<dyn Write>,
+    filter: TraceFilter,
+    error: Option<io::Error>,
+}
+
+impl Tracer {
+    pub fn new(writer: Box<dyn Write>, filter: TraceFilter) -> Self {
+        Self {
+            writer,
+            filter,
+            error: None,
+        }
+    }
+
+    /// Creates a tracer writing to the file at `path`.
+    pub fn create(path: &str, filter: TraceFilter) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(Box::new(BufWriter::new(file)), filter))
+    }
+
+    /// Flushes the trace, reporting the first write error if there was one.
+    /// Tracing stops silently after a write error so emulation is unaffected.
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.writer.flush(),
+        }
+    }
+
+    /// Captures the state an instruction is about to change, or `None` if
+    /// the instruction is filtered out.
+    pub(crate) fn before(&self, cpu: &Cpu, ram: &Ram, timer: &Timer) -> Option<TraceState> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        let pc = cpu.program_counter.get_value();
+        let opcode = ram.get_instruction(pc).ok()?;
+
+        self.filter
+            .matches(pc, opcode)
+            .then(|| TraceState::capture(cpu, ram, timer))
+    }
+
+    /// Writes the record for an instruction that started in `before`.
+    pub(crate) fn record(&mut self, cycle: u64, before: &TraceState, after: &TraceState) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let text = disassembler::decode(&before.bytes).map_or("???".to_string(), |(text, _)| text);
+        let mut line = format!(
+            "{:08} {:04X} {:04X} {:<20}",
+            cycle,
+            before.pc,
+            before.opcode(),
+            text
+        );
+
+        for index in 0..16 {
+            if before.v[index] != after.v[index] {
+                line.push_str(&format!(" V{:X}={:02X}", index, after.v[index]));
+            }
+        }
+        if before.i != after.i {
+            line.push_str(&format!(" I={:04X}", after.i));
+        }
+        if before.stack_depth != after.stack_depth {
+            line.push_str(&format!(" SP={}", after.stack_depth));
+        }
+        if before.delay_timer != after.delay_timer {
+            line.push_str(&format!(" DT={:02X}", after.delay_timer));
+        }
+        if before.sound_timer != after.sound_timer {
+            line.push_str(&format!(" ST={:02X}", after.sound_timer));
+        }
+
+        if let Err(err) = writeln!(self.writer, "{}", line.trim_end()) {
+            self.error = Some(err);
+        }
+    }
+}
+
+/// The registers an instruction may change, captured around its execution.
+pub(crate) struct TraceState {
+    pc: u16,
+    bytes: [u8; 4],
+    v: [u8; 16],
+    i: u16,
+    stack_depth: usize,
+    delay_timer: u8,
+    sound_timer: u8,
+}
+
+impl TraceState {
+    pub(crate) fn capture(cpu: &Cpu, ram: &Ram, timer: &Timer) -> Self {
+        let pc = cpu.program_counter.get_value();
+        let mut bytes = [0; 4];
+        for (offset, byte) in bytes.iter_mut().enumerate() {
+            *byte = ram.read_byte(pc.wrapping_add(offset as u16)).unwrap_or(0);
+        }
+
+        Self {
+            pc,
+            bytes,
+            v: std::array::from_fn(|index| cpu.registers.get_vn(index as u8)),
+            i: cpu.registers.get_i(),
+            stack_depth: cpu.registers.stack().len(),
+            delay_timer: timer.get_delay_timer(),
+            sound_timer: timer.get_sound_timer(),
+        }
+    }
+
+    fn opcode(&self) -> u16 {
+        u16::from_be_bytes([self.bytes[0], self.bytes[1]])
+    }
+}
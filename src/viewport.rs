@@ -0,0 +1,68 @@
+//! Placing the display inside a window of any size.
+
+/// How the display is scaled to fill the window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Scaling {
+    /// Fill the whole window, distorting the pixels if its shape differs.
+    Stretch,
+    /// As large as fits while keeping the display's shape.
+    #[default]
+    Aspect,
+    /// Whole multiples of the display size only, so every pixel is the same
+    /// size.
+    Integer,
+}
+
+impl Scaling {
+    pub const NAMES: [&'static str; 3] = ["stretch", "aspect", "integer"];
+
+    /// Looks up a mode by one of [`Self::NAMES`], ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let index = Self::NAMES
+            .iter()
+            .position(|known| known.eq_ignore_ascii_case(name))?;
+        Some([Self::Stretch, Self::Aspect, Self::Integer][index])
+    }
+}
+
+/// The area of the window the display is drawn to. Anything outside it is
+/// border.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    /// Centres a `display` sized `(width, height)` in a `window` of the same
+    /// form.
+    pub fn fit(display: (u32, u32), window: (u32, u32), scaling: Scaling) -> Self {
+        let (width, height) = match scaling {
+            Scaling::Stretch => window,
+            // Windows smaller than the display cannot fit a whole multiple
+            Scaling::Integer if window.0 >= display.0 && window.1 >= display.1 => {
+                let scale = (window.0 / display.0).min(window.1 / display.1);
+                (display.0 * scale, display.1 * scale)
+            }
+            Scaling::Aspect | Scaling::Integer => {
+                let scale = f64::min(
+                    window.0 as f64 / display.0 as f64,
+                    window.1 as f64 / display.1 as f64,
+                );
+                (
+                    (display.0 as f64 * scale).round() as u32,
+                    (display.1 as f64 * scale).round() as u32,
+                )
+            }
+        };
+
+        Self {
+            x: (window.0.saturating_sub(width) / 2) as i32,
+            y: (window.1.saturating_sub(height) / 2) as i32,
+            width,
+            height,
+        }
+    }
+}
@@ -0,0 +1,17 @@
+; Moves a ball one pixel per frame and stores its position at `position`.
+include "constants.asm"
+
+    LD V0, START_X
+    LD V1, START_Y
+loop:
+    LD I, ball
+    DRW V0, V1, BALL_HEIGHT
+    ADD V0, 1
+    LD I, position
+    SAVE V0, V1
+    JP loop
+
+ball:
+    db 0b11000000, 0b11000000
+position:
+    dw 0x0000
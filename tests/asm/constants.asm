@@ -0,0 +1,3 @@
+START_X EQU 0x10
+START_Y EQU 8
+BALL_HEIGHT EQU 2
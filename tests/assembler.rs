@@ -0,0 +1,68 @@
+use std::fs;
+use std::path::Path;
+
+use chip8::assembler::{self, AsmError};
+use chip8::{disassembler, Rom};
+
+#[test]
+fn assembles_labels_constants_data_and_includes() {
+    let rom = assembler::assemble_file(Path::new("tests/asm/bounce.asm")).unwrap();
+
+    let expected = [
+        0x60, 0x10, // LD V0, START_X
+        0x61, 0x08, // LD V1, START_Y
+        0xA2, 0x10, // LD I, ball
+        0xD0, 0x12, // DRW V0, V1, BALL_HEIGHT
+        0x70, 0x01, // ADD V0, 1
+        0xA2, 0x12, // LD I, position
+        0x50, 0x12, // SAVE V0, V1
+        0x12, 0x04, // JP loop
+        0xC0, 0xC0, // ball
+        0x00, 0x00, // position
+    ];
+    assert_eq!(rom, expected);
+}
+
+#[test]
+fn reports_errors_with_line_numbers() {
+    let error = |source| assembler::assemble(source).unwrap_err();
+
+    assert_eq!(
+        error("CLS\nJP nowhere\n"),
+        AsmError {
+            file: None,
+            line: 2,
+            message: "undefined symbol `nowhere`".to_string(),
+        }
+    );
+    assert_eq!(error("CLS\n\nLD V0, 256").line, 3);
+    assert_eq!(
+        error("MOV V0, V1").to_string(),
+        "line 1: unknown instruction `MOV`"
+    );
+    assert_eq!(
+        error("DRW V0, 5").to_string(),
+        "line 1: invalid operands for `DRW`"
+    );
+    assert_eq!(error("a: CLS\na: CLS").line, 2);
+}
+
+#[test]
+fn long_index_load_takes_four_bytes() {
+    let rom = assembler::assemble("LD I, LONG target\ntarget: db 0xAB").unwrap();
+    assert_eq!(rom, [0xF0, 0x00, 0x02, 0x04, 0xAB]);
+}
+
+#[test]
+fn disassembled_roms_reassemble_to_the_same_bytes() {
+    for entry in fs::read_dir("roms").unwrap() {
+        let path = entry.unwrap().path();
+        let rom = Rom::build(path.to_str().unwrap()).unwrap();
+
+        let listing = disassembler::disassemble(&rom);
+        let reassembled = assembler::assemble(&listing)
+            .unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+
+        assert_eq!(reassembled, rom.data, "{}", path.display());
+    }
+}
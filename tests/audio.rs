@@ -0,0 +1,73 @@
+use chip8::audio::{AudioSink, Oscillator, Sound, WavSink, SAMPLE_RATE};
+use chip8::Quirks;
+
+mod common;
+
+use common::{machine_with, run};
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[test]
+fn wav_header_describes_the_samples() {
+    let mut sink = WavSink::new();
+    for sound in [Sound::Beep, Sound::Silent, Sound::Beep] {
+        sink.update(sound);
+    }
+
+    let mut wav = Vec::new();
+    sink.write_to(&mut wav).unwrap();
+
+    let samples = 3 * SAMPLE_RATE / 60;
+    assert_eq!(wav.len(), 44 + samples as usize);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32_at(&wav, 4), 36 + samples);
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u32_at(&wav, 24), SAMPLE_RATE);
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(u32_at(&wav, 40), samples);
+
+    // The silent frame sits at the midpoint, the beeps swing around it
+    let frame = (SAMPLE_RATE / 60) as usize;
+    let data = &wav[44..];
+    assert!(data[frame..2 * frame].iter().all(|sample| *sample == 128));
+    assert!(data[..frame].contains(&192) && data[..frame].contains(&64));
+}
+
+#[test]
+fn oscillator_plays_a_square_wave() {
+    let mut oscillator = Oscillator::default();
+    let rate = SAMPLE_RATE as f32;
+
+    assert_eq!(oscillator.next_sample(&Sound::Silent, rate), 0.0);
+
+    // One period of the 440 Hz beep is about 100 samples, half high and
+    // half low
+    let period: Vec<f32> = (0..100)
+        .map(|_| oscillator.next_sample(&Sound::Beep, rate))
+        .collect();
+    let high = period.iter().filter(|sample| **sample == 1.0).count();
+    assert!(period.iter().all(|sample| sample.abs() == 1.0));
+    assert!((48..=52).contains(&high), "{} high samples", high);
+}
+
+#[test]
+fn sound_plays_while_the_sound_timer_runs() {
+    let program = [
+        0x60, 0x03, // V0 = 3
+        0xF0, 0x18, // sound timer = V0
+    ];
+    let mut machine = machine_with(Quirks::default(), &program);
+    run(&mut machine, 1);
+    assert_eq!(machine.sound(), Sound::Silent);
+
+    run(&mut machine, 1);
+    for _ in 0..3 {
+        assert_eq!(machine.sound(), Sound::Beep);
+        assert!(machine.sound_active());
+        machine.tick_timers();
+    }
+    assert_eq!(machine.sound(), Sound::Silent);
+    assert!(!machine.sound_active());
+}
@@ -0,0 +1,37 @@
+//! Fixtures shared by the integration tests.
+
+// Each test file uses only some of these
+#![allow(dead_code)]
+
+use chip8::{Machine, Quirks, Rom};
+
+/// A machine with `program` loaded at 0x200 and the RNG seeded with 0.
+pub fn machine_with(quirks: Quirks, program: &[u8]) -> Machine {
+    let mut machine = Machine::new(quirks);
+    machine.seed_rng(0);
+    machine
+        .load_rom(Rom {
+            data: program.to_vec(),
+        })
+        .expect("Error loading ROM.");
+    machine
+}
+
+/// A COSMAC VIP machine with `roms/<file>` loaded and the RNG seeded with 0.
+pub fn machine_with_rom(file: &str) -> Machine {
+    let rom = Rom::build(&format!("roms/{}", file)).expect("Error reading ROM.");
+    let mut machine = Machine::new(Quirks::default());
+    machine.seed_rng(0);
+    machine.load_rom(rom).expect("Error loading ROM.");
+    machine
+}
+
+pub fn run(machine: &mut Machine, instructions: usize) {
+    for _ in 0..instructions {
+        machine.run_instruction().expect("Instruction faulted.");
+    }
+}
+
+pub fn pc(machine: &Machine) -> u16 {
+    machine.cpu().program_counter.get_value()
+}
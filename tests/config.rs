@@ -0,0 +1,79 @@
+use chip8::config::Config;
+use chip8::keymap::KeyMap;
+
+#[test]
+fn cosmac_layout_is_the_default() {
+    let config = Config::parse("").unwrap();
+    let keymap = config.keymap_for("PONG");
+
+    assert_eq!(keymap, &KeyMap::cosmac());
+    assert_eq!(keymap.key_for("1"), Some(0x1));
+    assert_eq!(keymap.key_for("4"), Some(0xC));
+    assert_eq!(keymap.key_for("x"), Some(0x0));
+    assert_eq!(keymap.key_for("V"), Some(0xF));
+    assert_eq!(keymap.key_for("0"), None);
+}
+
+#[test]
+fn roms_override_the_global_layout() {
+    let config = Config::parse(
+        r#"
+        [keyboard]
+        layout = "hex"
+        keys = { Space = "5", Left = 7 }
+
+        [roms.TETRIS.keyboard]
+        keys = { Up = "4" }
+
+        [roms.PONG.keyboard]
+        layout = "Cosmac"
+        "#,
+    )
+    .unwrap();
+
+    let global = config.keymap_for("BRIX");
+    assert_eq!(global.key_for("A"), Some(0xA));
+    assert_eq!(global.key_for("space"), Some(0x5));
+    assert_eq!(global.key_for("Left"), Some(0x7));
+
+    let tetris = config.keymap_for("TETRIS");
+    assert_eq!(tetris.key_for("Up"), Some(0x4));
+    assert_eq!(tetris.key_for("Space"), Some(0x5));
+
+    assert_eq!(config.keymap_for("PONG"), &KeyMap::cosmac());
+}
+
+#[test]
+fn mistakes_are_reported() {
+    let error = |source| Config::parse(source).unwrap_err();
+
+    assert!(error("[keyboard]\nlayout = \"dvorak\"").contains("unknown layout `dvorak`"));
+    assert!(error("[keyboard]\nkeys = { Q = \"G\" }").contains("keyboard.keys.Q"));
+    assert!(error("[keybaord]").contains("unknown setting `keybaord`"));
+    assert!(error("[roms.PONG]\nlayout = \"hex\"").contains("roms.PONG.layout"));
+    assert!(!error("[keyboard").is_empty());
+}
+
+#[test]
+fn controller_buttons_can_be_mapped_per_rom() {
+    let config = Config::parse(
+        r#"
+        [controller]
+        buttons = { start = "F" }
+
+        [roms.TANK.controller]
+        buttons = { x = 0 }
+        "#,
+    )
+    .unwrap();
+
+    let global = config.controller_map_for("BRIX");
+    assert_eq!(global.key_for("dpleft"), Some(0x4));
+    assert_eq!(global.key_for("a"), Some(0x5));
+    assert_eq!(global.key_for("start"), Some(0xF));
+    assert_eq!(global.key_for("x"), None);
+
+    let tank = config.controller_map_for("TANK");
+    assert_eq!(tank.key_for("x"), Some(0x0));
+    assert_eq!(tank.key_for("start"), Some(0xF));
+}
@@ -0,0 +1,83 @@
+use chip8::debugger::{Breakpoint, Debugger, StopReason, Watchpoint};
+use chip8::Quirks;
+
+mod common;
+
+use common::{machine_with, pc};
+
+#[test]
+fn breakpoints_stop_before_the_instruction() {
+    // 6001 6102 6203 A300 1208
+    let mut machine = machine_with(
+        Quirks::default(),
+        &[0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0xA3, 0x00, 0x12, 0x08],
+    );
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(Breakpoint::Address(0x204));
+    debugger.add_breakpoint(Breakpoint::opcode_pattern("A???").unwrap());
+
+    assert_eq!(debugger.run(&mut machine, 100), StopReason::Breakpoint(0));
+    assert_eq!(pc(&machine), 0x204);
+    assert_eq!(machine.cpu().registers.get_vn(2), 0);
+
+    assert_eq!(debugger.run(&mut machine, 100), StopReason::Breakpoint(1));
+    assert_eq!(pc(&machine), 0x206);
+
+    assert_eq!(debugger.run(&mut machine, 1), StopReason::Done);
+    assert_eq!(machine.cpu().registers.get_i(), 0x300);
+    assert_eq!(debugger.cycles(), 4);
+}
+
+#[test]
+fn watchpoints_report_changes() {
+    // 6005 7001 A300 F055 1208
+    let mut machine = machine_with(
+        Quirks::default(),
+        &[0x60, 0x05, 0x70, 0x01, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x08],
+    );
+    let mut debugger = Debugger::new();
+    debugger.add_watchpoint(Watchpoint::Register(0));
+    debugger.add_watchpoint(Watchpoint::Memory(0x300));
+
+    let stop = debugger.run(&mut machine, 100);
+    assert_eq!(
+        stop,
+        StopReason::Watchpoint {
+            index: 0,
+            old: 0,
+            new: 5
+        }
+    );
+
+    let stop = debugger.run(&mut machine, 100);
+    assert_eq!(
+        stop,
+        StopReason::Watchpoint {
+            index: 0,
+            old: 5,
+            new: 6
+        }
+    );
+
+    let stop = debugger.run(&mut machine, 100);
+    assert_eq!(
+        stop,
+        StopReason::Watchpoint {
+            index: 1,
+            old: 0,
+            new: 6
+        }
+    );
+    assert_eq!(pc(&machine), 0x208);
+}
+
+#[test]
+fn errors_stop_execution() {
+    let mut machine = machine_with(Quirks::default(), &[0x00, 0xEE]); // return with an empty stack
+    let mut debugger = Debugger::new();
+
+    assert!(matches!(
+        debugger.run(&mut machine, 10),
+        StopReason::Error(_)
+    ));
+}
@@ -0,0 +1,51 @@
+use chip8::disassembler;
+use chip8::Rom;
+
+#[test]
+fn decodes_single_instructions() {
+    assert_eq!(
+        disassembler::decode(&[0xD0, 0x1F]),
+        Some(("DRW V0, V1, 15".to_string(), 2))
+    );
+    assert_eq!(
+        disassembler::decode(&[0xF0, 0x00, 0x12, 0x34]),
+        Some(("LD I, LONG 0x1234".to_string(), 4))
+    );
+    assert_eq!(disassembler::decode(&[0x81, 0x2F]), None);
+    assert_eq!(disassembler::decode(&[0x60]), None);
+}
+
+#[test]
+fn separates_code_from_data_and_labels_targets() {
+    let rom = Rom {
+        data: vec![
+            0x22, 0x08, // call 0x208
+            0xA2, 0x0E, // I = 0x20E
+            0x12, 0x04, // jump to self
+            0xFF, 0xFF, // unreachable
+            0x30, 0x00, // skip if V0 == 0
+            0x61, 0x01, // V1 = 1
+            0x00, 0xEE, // return
+            0x3C, 0x42, // sprite data
+        ],
+    };
+
+    let expected = "\
+; 16 bytes
+    CALL sub_208            ; 200: 2208
+    LD I, data_20E          ; 202: A20E
+
+label_204:
+    JP label_204            ; 204: 1204
+    db 0xFF, 0xFF           ; 206
+
+sub_208:
+    SE V0, 0x00             ; 208: 3000
+    LD V1, 0x01             ; 20A: 6101
+    RET                     ; 20C: 00EE
+
+data_20E:
+    db 0x3C, 0x42           ; 20E
+";
+    assert_eq!(disassembler::disassemble(&rom), expected);
+}
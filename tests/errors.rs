@@ -0,0 +1,86 @@
+use chip8::{ExecError, Machine, Quirks, Rom};
+
+mod common;
+
+use common::machine_with;
+
+/// Runs until an instruction fails, then checks that the machine still
+/// works: the fault repeats without changing anything and the state can be
+/// saved and restored.
+fn run_until_error(machine: &mut Machine) -> ExecError {
+    let error = (0..1000)
+        .find_map(|_| machine.run_instruction().err())
+        .expect("No instruction faulted.");
+
+    let state = machine.save_state();
+    assert_eq!(machine.run_instruction(), Err(error));
+    assert_eq!(machine.save_state(), state);
+    assert_eq!(machine.load_state(&state), Ok(()));
+
+    error
+}
+
+#[test]
+fn unknown_opcodes_are_reported() {
+    let mut machine = machine_with(Quirks::COSMAC_VIP, &[0x60, 0x01, 0xE0, 0xFF]);
+
+    assert_eq!(
+        run_until_error(&mut machine),
+        ExecError::UnknownOpcode {
+            address: 0x202,
+            opcode: 0xE0FF
+        }
+    );
+    assert_eq!(machine.cpu().program_counter.get_value(), 0x202);
+    assert_eq!(machine.cpu().registers.get_vn(0), 1);
+}
+
+#[test]
+fn return_with_an_empty_stack_underflows() {
+    let mut machine = machine_with(Quirks::COSMAC_VIP, &[0x00, 0xEE]);
+
+    assert_eq!(run_until_error(&mut machine), ExecError::StackUnderflow);
+    assert_eq!(machine.cpu().program_counter.get_value(), 0x200);
+}
+
+#[test]
+fn seventeen_nested_calls_overflow() {
+    // Calls itself forever
+    let mut machine = machine_with(Quirks::COSMAC_VIP, &[0x22, 0x00]);
+
+    assert_eq!(run_until_error(&mut machine), ExecError::StackOverflow);
+    assert_eq!(machine.cycles(), 16);
+    assert_eq!(machine.cpu().registers.stack(), &[0x202; 16]);
+}
+
+#[test]
+fn program_counter_leaving_memory_is_reported() {
+    let mut machine = machine_with(Quirks::COSMAC_VIP, &[0x1F, 0xFF]);
+
+    assert_eq!(
+        run_until_error(&mut machine),
+        ExecError::PcOutOfBounds { address: 0xFFF }
+    );
+}
+
+#[test]
+fn roms_too_large_for_memory_are_rejected() {
+    let mut machine = Machine::new(Quirks::COSMAC_VIP);
+    let rom = Rom {
+        data: vec![0; 0x1000 - 0x200 + 1],
+    };
+
+    assert_eq!(
+        machine.load_rom(rom),
+        Err(ExecError::MemoryOutOfBounds { address: 0x1000 })
+    );
+
+    // A ROM that fits can still be loaded and run
+    machine
+        .load_rom(Rom {
+            data: vec![0x60, 0x2A],
+        })
+        .unwrap();
+    assert_eq!(machine.run_instruction(), Ok(()));
+    assert_eq!(machine.cpu().registers.get_vn(0), 0x2A);
+}
@@ -0,0 +1,114 @@
+//! Runs the bundled test ROMs headlessly and compares the final display
+//! against the reference framebuffers in `tests/golden/`.
+//!
+//! Set `UPDATE_GOLDEN=1` to rewrite the references from the current output.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use chip8::audio::NullSink;
+use chip8::headless::{self, KeyScript, RunLimit};
+use chip8::{Machine, Quirks, Rom};
+
+fn run_rom(file_name: &str, quirks: Quirks, limit: RunLimit, script: &KeyScript) -> String {
+    let rom = Rom::build(&format!("roms/{}", file_name)).expect("Error reading ROM.");
+    let mut machine = Machine::new(quirks);
+    machine.seed_rng(0);
+    machine.load_rom(rom).expect("Error loading ROM.");
+
+    headless::run(&mut machine, limit, script, &mut NullSink).expect("ROM faulted.");
+
+    headless::display_to_ascii(machine.ppu())
+}
+
+fn assert_golden(name: &str, actual: &str) {
+    let path: PathBuf = ["tests", "golden", &format!("{}.txt", name)]
+        .iter()
+        .collect();
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, actual).expect("Error writing golden file.");
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).expect("Error reading golden file.");
+    assert!(
+        expected == actual,
+        "display does not match {}\n\nexpected:\n{}\nactual:\n{}",
+        path.display(),
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn chip8_logo() {
+    let display = run_rom(
+        "1-chip8-logo.ch8",
+        Quirks::default(),
+        RunLimit::Frames(100),
+        &KeyScript::new(),
+    );
+    assert_golden("1-chip8-logo", &display);
+}
+
+#[test]
+fn ibm_logo() {
+    let display = run_rom(
+        "2-ibm-logo.ch8",
+        Quirks::default(),
+        RunLimit::Frames(100),
+        &KeyScript::new(),
+    );
+    assert_golden("2-ibm-logo", &display);
+}
+
+#[test]
+fn corax_plus() {
+    let display = run_rom(
+        "3-corax+.ch8",
+        Quirks::default(),
+        RunLimit::Frames(200),
+        &KeyScript::new(),
+    );
+    assert_golden("3-corax+", &display);
+}
+
+#[test]
+fn flags() {
+    let display = run_rom(
+        "4-flags.ch8",
+        Quirks::default(),
+        RunLimit::Frames(200),
+        &KeyScript::new(),
+    );
+    assert_golden("4-flags", &display);
+}
+
+fn run_quirks(menu_key: u8, quirks: Quirks) -> String {
+    // Pick the platform from the menu
+    let mut script = KeyScript::new();
+    script.push(100, 1 << menu_key);
+    script.push(130, 0);
+
+    run_rom("5-quirks.ch8", quirks, RunLimit::Frames(1500), &script)
+}
+
+#[test]
+fn quirks_cosmac_vip() {
+    let display = run_quirks(0x1, Quirks::COSMAC_VIP);
+    assert_golden("5-quirks-cosmac-vip", &display);
+}
+
+#[test]
+fn quirks_super_chip() {
+    let display = run_quirks(0x2, Quirks::SUPER_CHIP);
+    assert_golden("5-quirks-super-chip", &display);
+}
+
+#[test]
+fn quirks_xo_chip() {
+    let display = run_quirks(0x3, Quirks::XO_CHIP);
+    assert_golden("5-quirks-xo-chip", &display);
+}
@@ -0,0 +1,80 @@
+use chip8::audio::NullSink;
+use chip8::headless::{self, KeyScript, RunLimit};
+use chip8::Quirks;
+
+mod common;
+
+use common::{machine_with, run};
+
+#[test]
+fn huge_frame_limits_do_not_overflow() {
+    // 00FD exits during the first frame
+    let mut machine = machine_with(Quirks::SUPER_CHIP, &[0x00, 0xFD]);
+    machine.set_instructions_per_frame(1000);
+
+    let cycles = headless::run(
+        &mut machine,
+        RunLimit::Frames(u64::MAX),
+        &KeyScript::new(),
+        &mut NullSink,
+    );
+
+    assert_eq!(cycles, Ok(1000));
+    assert!(machine.has_exited());
+}
+
+#[test]
+fn key_scripts_are_parsed_in_frame_order() {
+    let script = KeyScript::parse("# comment\n\n30 -\n10 5\n  20 4F  \n").unwrap();
+
+    assert_eq!(
+        script.events(),
+        &[(10, 1 << 0x5), (20, 1 << 0x4 | 1 << 0xF), (30, 0)]
+    );
+    assert_eq!(script.keys_at(9), 0);
+    assert_eq!(script.keys_at(25), 1 << 0x4 | 1 << 0xF);
+}
+
+#[test]
+fn malformed_key_script_lines_are_reported() {
+    let error = |source| KeyScript::parse(source).unwrap_err();
+
+    assert_eq!(error("10 5\n20"), "line 2: expected `<frame> <keys>`");
+    assert_eq!(error("10 5 6"), "line 1: expected `<frame> <keys>`");
+    assert_eq!(error("x 5"), "line 1: invalid frame `x`");
+    assert_eq!(error("-1 5"), "line 1: invalid frame `-1`");
+    assert_eq!(error("10 G"), "line 1: invalid keys `G`");
+}
+
+#[test]
+fn displays_and_registers_are_dumped() {
+    let program = [
+        0x60, 0x00, // V0 = 0
+        0xF0, 0x29, // I = font sprite for "0"
+        0xD0, 0x05, // draw it at 0,0
+        0x6F, 0xAB, // VF = 0xAB
+        0x22, 0x0C, // call 0x20C
+        0x00, 0x00, //
+        0x00, 0xE0, // clear the screen, not reached
+    ];
+    let mut machine = machine_with(Quirks::SUPER_CHIP, &program);
+    run(&mut machine, 5);
+
+    let pbm = headless::display_to_pbm(machine.ppu());
+    let lines: Vec<&str> = pbm.lines().collect();
+    assert_eq!(lines[..2], ["P1", "64 32"]);
+    assert_eq!(lines.len(), 2 + 32);
+    assert_eq!(lines[2], format!("1111{}", "0".repeat(60)));
+    assert_eq!(lines[3], format!("1001{}", "0".repeat(60)));
+    assert_eq!(lines[7], "0".repeat(64));
+
+    let registers = headless::registers_to_string(&machine);
+    let lines: Vec<&str> = registers.lines().collect();
+    assert_eq!(lines[0], "PC: 0x020C  I: 0x0000");
+    assert_eq!(
+        lines[1],
+        "V0: 0x00 V1: 0x00 V2: 0x00 V3: 0x00 V4: 0x00 V5: 0x00 V6: 0x00 V7: 0x00"
+    );
+    assert!(lines[2].ends_with("VF: 0xAB"));
+    assert_eq!(lines[3], "Stack: [0x020A]");
+}
@@ -0,0 +1,63 @@
+use chip8::headless::{self, KeyScript};
+use chip8::Quirks;
+
+mod common;
+
+use common::{machine_with, pc, run};
+
+#[test]
+fn keys_are_tracked_independently() {
+    let program = [
+        0x60, 0x04, // V0 = 4
+        0x61, 0x06, // V1 = 6
+        0xE0, 0x9E, // skip if key V0 is pressed
+        0x12, 0x04, // loop
+        0xE1, 0x9E, // skip if key V1 is pressed
+        0x12, 0x08, // loop
+        0x12, 0x0C, // done
+    ];
+    let mut machine = machine_with(Quirks::default(), &program);
+    machine.press_key(4);
+    machine.press_key(6);
+    machine.release_key(4);
+    run(&mut machine, 4);
+    assert_eq!(pc(&machine), 0x204);
+    assert!(machine.keyboard().is_pressed(6));
+
+    machine.press_key(4);
+    run(&mut machine, 3);
+    assert_eq!(pc(&machine), 0x20C);
+}
+
+#[test]
+fn wait_for_key_needs_a_release() {
+    let program = [
+        0xF0, 0x0A, // V0 = wait for key
+        0x12, 0x02, // done
+    ];
+    let mut machine = machine_with(Quirks::default(), &program);
+    machine.press_key(0xB);
+    machine.press_key(0x3);
+    run(&mut machine, 3);
+    assert_eq!(pc(&machine), 0x200);
+
+    // Releasing another key does not end the wait
+    machine.release_key(0xB);
+    run(&mut machine, 1);
+    assert_eq!(pc(&machine), 0x200);
+
+    machine.release_key(0x3);
+    run(&mut machine, 1);
+    assert_eq!(pc(&machine), 0x202);
+    assert_eq!(machine.cpu().registers.get_vn(0), 0x3);
+}
+
+#[test]
+fn key_scripts_hold_several_keys() {
+    let script = KeyScript::parse("10 46\n20 -\n30 f\n").unwrap();
+    assert_eq!(script.keys_at(15), 0x0050);
+    assert_eq!(script.keys_at(25), 0);
+    assert_eq!(script.keys_at(30), 0x8000);
+    assert_eq!(headless::keys_to_string(0x0050), "46");
+    assert!(KeyScript::parse("10 4g\n").is_err());
+}
@@ -0,0 +1,50 @@
+use chip8::audio::NullSink;
+use chip8::headless::{self, RunLimit};
+use chip8::movie::Movie;
+use chip8::Quirks;
+
+mod common;
+
+use common::machine_with_rom;
+
+#[test]
+fn replaying_a_movie_reproduces_the_session() {
+    let mut machine = machine_with_rom("BRIX");
+    let mut movie = Movie::new(machine.rom_hash(), 42, Quirks::COSMAC_VIP);
+    movie.instructions_per_frame = 25;
+    movie.apply(&mut machine).unwrap();
+
+    let input = [(10, 0x0010), (40, 0x0050), (55, 0x0040), (90, 0)];
+    for frame in 0..120 {
+        if let Some((_, keys)) = input.iter().find(|(at, _)| *at == frame) {
+            machine.set_keys(*keys);
+        }
+        movie.record(machine.frames(), machine.keyboard().pressed());
+        machine.run_frame().expect("Instruction faulted.");
+    }
+    assert_eq!(movie.frames, 120);
+
+    let movie = Movie::parse(&movie.to_string()).unwrap();
+    let mut replay = machine_with_rom("BRIX");
+    movie.apply(&mut replay).unwrap();
+    assert_eq!(replay.instructions_per_frame(), 25);
+    headless::run(
+        &mut replay,
+        RunLimit::Frames(movie.frames),
+        &movie.keys,
+        &mut NullSink,
+    )
+    .expect("Instruction faulted.");
+
+    assert_eq!(replay.save_state(), machine.save_state());
+}
+
+#[test]
+fn movies_check_the_rom() {
+    let brix = machine_with_rom("BRIX");
+    let movie = Movie::new(brix.rom_hash(), 1, Quirks::default());
+
+    let mut pong = machine_with_rom("PONG");
+    assert!(movie.apply(&mut pong).is_err());
+    assert!(Movie::parse("chip8-movie 9\n").is_err());
+}
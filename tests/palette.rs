@@ -0,0 +1,37 @@
+use chip8::config::Config;
+use chip8::palette::{Color, Palette};
+
+#[test]
+fn palettes_parse_two_or_four_colours() {
+    assert_eq!(Palette::parse("classic").unwrap(), Palette::CLASSIC);
+
+    let palette = Palette::parse("#102030, FFB000").unwrap();
+    assert_eq!(palette.color(0), Color::rgb(0x10, 0x20, 0x30));
+    assert_eq!(palette.color(1), Color::rgb(0xFF, 0xB0, 0x00));
+    assert_eq!(palette.color(2), Palette::CLASSIC.color(2));
+
+    let palette = Palette::parse("000000,111111,222222,333333").unwrap();
+    assert_eq!(palette.color(3), Color::rgb(0x33, 0x33, 0x33));
+
+    assert!(Palette::parse("000000").is_err());
+    assert!(Palette::parse("000000,GGGGGG").is_err());
+}
+
+#[test]
+fn themes_are_selected_by_name() {
+    for (name, theme) in Palette::THEME_NAMES.into_iter().zip(Palette::THEMES) {
+        assert_eq!(Palette::parse(name).unwrap(), theme);
+    }
+    assert_eq!(
+        Palette::parse("octo").unwrap().color(0),
+        Color::rgb(0x99, 0x66, 0x00)
+    );
+    assert!(Palette::parse("sepia")
+        .unwrap_err()
+        .contains("unknown theme"));
+
+    let config = Config::parse("[display]\npalette = \"amber\"").unwrap();
+    assert_eq!(config.palette, Some(Palette::AMBER));
+    assert_eq!(Config::parse("").unwrap().palette, None);
+    assert!(Config::parse("[display]\npalette = 1").is_err());
+}
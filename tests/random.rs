@@ -0,0 +1,47 @@
+use chip8::audio::NullSink;
+use chip8::headless::{self, KeyScript, RunLimit};
+use chip8::{Machine, Quirks, Rng, Rom};
+
+fn run_maze(seed: u64) -> String {
+    let rom = Rom::build("roms/MAZE").expect("Error reading ROM.");
+    let mut machine = Machine::new(Quirks::default());
+    machine.seed_rng(seed);
+    machine.load_rom(rom).expect("Error loading ROM.");
+
+    headless::run(
+        &mut machine,
+        RunLimit::Frames(100),
+        &KeyScript::new(),
+        &mut NullSink,
+    )
+    .expect("ROM faulted.");
+
+    headless::display_to_ascii(machine.ppu())
+}
+
+#[test]
+fn same_seed_reproduces_run() {
+    assert_eq!(run_maze(42), run_maze(42));
+    assert_ne!(run_maze(42), run_maze(43));
+}
+
+#[test]
+fn fixed_sequence_feeds_cxnn() {
+    // C0FF C1FF C20F C3FF
+    let rom = Rom {
+        data: vec![0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0x0F, 0xC3, 0xFF],
+    };
+    let mut machine = Machine::new(Quirks::default());
+    machine.set_rng(Rng::sequence(vec![0x12, 0x34, 0x56]));
+    machine.load_rom(rom).expect("Error loading ROM.");
+
+    for _ in 0..4 {
+        machine.run_instruction().expect("Instruction faulted.");
+    }
+
+    let registers = &machine.cpu().registers;
+    assert_eq!(registers.get_vn(0x0), 0x12);
+    assert_eq!(registers.get_vn(0x1), 0x34);
+    assert_eq!(registers.get_vn(0x2), 0x06);
+    assert_eq!(registers.get_vn(0x3), 0x12);
+}
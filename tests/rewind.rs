@@ -0,0 +1,52 @@
+use chip8::rewind::RewindBuffer;
+
+mod common;
+
+use common::machine_with_rom;
+
+#[test]
+fn steps_back_through_recorded_frames() {
+    let mut machine = machine_with_rom("MAZE");
+    let mut rewind = RewindBuffer::new(100);
+    let mut states = vec![machine.save_state()];
+    rewind.push(machine.save_state());
+
+    for _ in 0..60 {
+        machine.run_frame().expect("Instruction faulted.");
+        states.push(machine.save_state());
+        rewind.push(machine.save_state());
+    }
+    assert_eq!(rewind.len(), 61);
+
+    // Deltas are much smaller than the full states
+    assert!(rewind.memory_used() < states[0].len() * 3);
+
+    for expected in states.iter().rev().skip(1) {
+        let state = rewind.step_back().expect("Ran out of history.");
+        assert_eq!(state, expected.as_slice());
+    }
+    assert_eq!(rewind.step_back(), None);
+
+    machine.load_state(&states[0]).unwrap();
+    assert_eq!(machine.cycles(), 0);
+}
+
+#[test]
+fn drops_the_oldest_frames_when_full() {
+    let mut machine = machine_with_rom("MAZE");
+    let mut rewind = RewindBuffer::new(10);
+    let mut states = Vec::new();
+
+    for _ in 0..25 {
+        machine.run_frame().expect("Instruction faulted.");
+        states.push(machine.save_state());
+        rewind.push(machine.save_state());
+    }
+    assert_eq!(rewind.len(), 10);
+
+    let mut oldest = None;
+    while let Some(state) = rewind.step_back() {
+        oldest = Some(state.to_vec());
+    }
+    assert_eq!(oldest.as_ref(), Some(&states[15]));
+}
@@ -0,0 +1,99 @@
+use std::fs;
+
+use chip8::config::Config;
+use chip8::keymap::KeyMap;
+use chip8::palette::Palette;
+use chip8::quirks::IndexIncrement;
+use chip8::romdb::{Platform, RomDatabase};
+use chip8::Rom;
+
+#[test]
+fn bundled_database_covers_the_shipped_roms() {
+    let database = RomDatabase::bundled();
+
+    for entry in fs::read_dir("roms").unwrap() {
+        let path = entry.unwrap().path();
+        let rom = Rom::build(path.to_str().unwrap()).unwrap();
+        let entry = database.get(&rom.sha1());
+
+        assert!(
+            entry.is_some_and(|entry| entry.title.is_some()),
+            "{} is missing",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn entries_adjust_the_platform_defaults() {
+    let database = RomDatabase::parse(
+        r#"
+        [0000000000000000000000000000000000000001]
+        platform = "schip"
+        quirks = { clipping = false, index_increment = "x" }
+        ipf = 30
+        palette = "000000,FFB000"
+        keyboard = { keys = { Space = "5" } }
+
+        [0000000000000000000000000000000000000002]
+        keyboard = "hex"
+        "#,
+    )
+    .unwrap();
+
+    let mut sha1 = [0; 20];
+    sha1[19] = 1;
+    let entry = database.get(&sha1).unwrap();
+    assert_eq!(entry.platform, Some(Platform::SuperChip));
+    let quirks = entry.quirks.unwrap();
+    assert!(quirks.shifting && !quirks.clipping);
+    assert_eq!(quirks.index_increment, IndexIncrement::X);
+    assert_eq!(entry.instructions_per_frame, Some(30));
+    assert_eq!(
+        entry.palette,
+        Some(Palette::parse("000000,FFB000").unwrap())
+    );
+
+    let keymap = entry.keymap(&KeyMap::cosmac());
+    assert_eq!(keymap.key_for("Space"), Some(0x5));
+    assert_eq!(keymap.key_for("Q"), Some(0x4));
+
+    sha1[19] = 2;
+    let entry = database.get(&sha1).unwrap();
+    assert_eq!(entry.quirks, None);
+    assert_eq!(entry.keymap(&KeyMap::cosmac()), KeyMap::hex());
+}
+
+fn error(settings: &str) -> String {
+    let source = format!("[0000000000000000000000000000000000000001]\n{}", settings);
+    RomDatabase::parse(&source).unwrap_err()
+}
+
+#[test]
+fn mistakes_are_reported() {
+    assert!(RomDatabase::parse("[PONG]")
+        .unwrap_err()
+        .contains("not a SHA-1"));
+    assert!(error("platform = \"nes\"").contains("unknown platform"));
+    assert!(error("quirks = { wrap = true }").contains("unknown quirk"));
+    assert!(error("ipf = 0").contains("ipf"));
+    assert!(error("speed = 10").contains("unknown setting"));
+}
+
+#[test]
+fn per_rom_keyboard_config_beats_the_database() {
+    let database = RomDatabase::bundled();
+    let brix = Rom::build("roms/BRIX").unwrap();
+    let entry = database.get(&brix.sha1()).unwrap();
+
+    // Without a per-ROM section the database adds to the global map
+    let config = Config::parse("[keyboard]\nkeys = { Space = \"5\" }").unwrap();
+    let keymap = config.keymap_for_entry("BRIX", entry);
+    assert_eq!(keymap.key_for("Left"), Some(0x4));
+    assert_eq!(keymap.key_for("Space"), Some(0x5));
+
+    let config = Config::parse("[roms.BRIX.keyboard]\nkeys = { Left = \"7\" }").unwrap();
+    let keymap = config.keymap_for_entry("BRIX", entry);
+    assert_eq!(keymap.key_for("Left"), Some(0x7));
+    assert_eq!(keymap, *config.keymap_for("BRIX"));
+}
@@ -0,0 +1,53 @@
+use std::time::{Duration, Instant};
+
+use chip8::scheduler::{FrameScheduler, MAX_CATCH_UP};
+
+const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+#[test]
+fn frames_keep_pace_with_elapsed_time() {
+    let start = Instant::now();
+    let mut scheduler = FrameScheduler::new(start);
+
+    assert_eq!(scheduler.frames_due(start), 1);
+    assert_eq!(scheduler.frames_due(start + FRAME / 2), 0);
+    // A slow frame is made up for on the next call
+    assert_eq!(scheduler.frames_due(start + FRAME * 3), 3);
+    assert_eq!(scheduler.next_frame(), start + FRAME * 4);
+
+    // Over a second, exactly 60 frames run however the calls are spaced
+    let mut frames = 0;
+    for millis in (0..1000).step_by(7) {
+        frames += scheduler.frames_due(start + FRAME * 4 + Duration::from_millis(millis));
+    }
+    frames += scheduler.frames_due(start + FRAME * 64 - Duration::from_nanos(1));
+    assert_eq!(frames, 60);
+}
+
+#[test]
+fn long_stalls_are_skipped() {
+    let start = Instant::now();
+    let mut scheduler = FrameScheduler::new(start);
+
+    let later = start + Duration::from_secs(10);
+    assert_eq!(scheduler.frames_due(later), MAX_CATCH_UP);
+    assert_eq!(scheduler.frames_due(later), 0);
+    assert_eq!(scheduler.next_frame(), later + FRAME);
+}
+
+#[test]
+fn speed_scales_the_frame_rate() {
+    let frames_in_a_second = |speed| {
+        let start = Instant::now();
+        let mut scheduler = FrameScheduler::new(start);
+        scheduler.set_speed(speed);
+        let frames: u32 = (0..990)
+            .map(|millis| scheduler.frames_due(start + Duration::from_millis(millis)))
+            .sum();
+        frames + scheduler.frames_due(start + Duration::from_secs(1) - FRAME / 8)
+    };
+
+    assert_eq!(frames_in_a_second(1.0), 60);
+    assert_eq!(frames_in_a_second(4.0), 240);
+    assert_eq!(frames_in_a_second(0.25), 15);
+}
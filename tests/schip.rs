@@ -0,0 +1,115 @@
+use chip8::{assembler, disassembler};
+use chip8::{Quirks, HIRES_HEIGHT, HIRES_WIDTH};
+
+mod common;
+
+use common::{machine_with, run};
+
+#[test]
+fn hires_mode_draws_16x16_sprites() {
+    let mut program = vec![
+        0x00, 0xFF, // hires
+        0xA2, 0x0A, // I = 0x20A
+        0x60, 0x00, // V0 = 0
+        0xD0, 0x00, // draw 16x16 at (V0, V0)
+        0x12, 0x08, // loop
+    ];
+    program.extend([0xFF; 32]);
+
+    let mut machine = machine_with(Quirks::SUPER_CHIP, &program);
+    run(&mut machine, 4);
+
+    assert_eq!(machine.resolution(), (HIRES_WIDTH, HIRES_HEIGHT));
+    let display = machine.display();
+    for row in display.iter().take(16) {
+        assert!(row[..16].iter().all(|pixel| *pixel == 1));
+        assert_eq!(row[16], 0);
+    }
+    assert!(display[16][..16].iter().all(|pixel| *pixel == 0));
+}
+
+#[test]
+fn scrolling_moves_the_display() {
+    let program = [
+        0xA0, 0x00, // I = font sprite for 0
+        0x60, 0x00, // V0 = 0
+        0xD0, 0x05, // draw at (0, 0)
+        0x00, 0xC2, // scroll down 2
+        0x00, 0xFB, // scroll right 4
+    ];
+
+    let mut machine = machine_with(Quirks::SUPER_CHIP, &program);
+    run(&mut machine, 5);
+
+    let display = machine.display();
+    assert_eq!(display[0][..8], [0; 8]);
+    assert_eq!(display[2][..8], [0, 0, 0, 0, 1, 1, 1, 1]);
+
+    // Scrolling left drops the pixels back to the left edge
+    let mut program = program.to_vec();
+    program.extend([0x00, 0xFC]);
+    let mut machine = machine_with(Quirks::SUPER_CHIP, &program);
+    run(&mut machine, 6);
+    assert_eq!(machine.display()[2][..8], [1, 1, 1, 1, 0, 0, 0, 0]);
+}
+
+#[test]
+fn scrolling_up_moves_the_selected_planes() {
+    let program = [
+        0xA0, 0x00, // I = font sprite for 0
+        0x60, 0x00, // V0 = 0
+        0x61, 0x03, // V1 = 3
+        0xD0, 0x15, // draw at (0, 3)
+        0x00, 0xD2, // scroll up 2
+        0xF2, 0x01, // select plane 2 only
+        0x00, 0xD1, // scroll up 1, leaving plane 1 alone
+    ];
+
+    let mut machine = machine_with(Quirks::XO_CHIP, &program);
+    run(&mut machine, 7);
+
+    let display = machine.display();
+    assert_eq!(display[0][..4], [0; 4]);
+    assert_eq!(display[1][..4], [1, 1, 1, 1]);
+    assert_eq!(display[2][..4], [1, 0, 0, 1]);
+    assert_eq!(display[5][..4], [1, 1, 1, 1]);
+    assert_eq!(display[6][..4], [0; 4]);
+    assert_eq!(
+        disassembler::decode(&[0x00, 0xD2]),
+        Some(("SCU 2".to_string(), 2))
+    );
+    assert_eq!(assembler::assemble("SCU 2").unwrap(), [0x00, 0xD2]);
+}
+
+#[test]
+fn big_font_and_rpl_flags() {
+    let program = [
+        0x60, 0x05, // V0 = 5
+        0x61, 0x07, // V1 = 7
+        0xF1, 0x75, // save V0-V1 to flags
+        0x60, 0x00, // V0 = 0
+        0x61, 0x00, // V1 = 0
+        0xF1, 0x85, // load V0-V1 from flags
+        0xF1, 0x30, // I = big font sprite for V1
+    ];
+
+    let mut machine = machine_with(Quirks::SUPER_CHIP, &program);
+    run(&mut machine, 7);
+
+    let registers = &machine.cpu().registers;
+    assert_eq!(registers.get_vn(0), 5);
+    assert_eq!(registers.get_vn(1), 7);
+    assert_eq!(registers.get_i(), chip8::ram::BIG_FONT_ADDRESS + 70);
+}
+
+#[test]
+fn exit_stops_execution() {
+    let program = [0x00, 0xFD, 0x60, 0x01];
+
+    let mut machine = machine_with(Quirks::SUPER_CHIP, &program);
+    run(&mut machine, 3);
+
+    assert!(machine.has_exited());
+    assert_eq!(machine.cpu().registers.get_vn(0), 0);
+    assert_eq!(machine.cpu().program_counter.get_value(), 0x200);
+}
@@ -0,0 +1,105 @@
+use chip8::audio::NullSink;
+use chip8::headless::{self, KeyScript, RunLimit};
+use chip8::state::{StateError, VERSION};
+use chip8::{Machine, Rng, Rom};
+
+mod common;
+
+use common::machine_with_rom;
+
+fn run_frames(machine: &mut Machine, frames: u64) {
+    headless::run(
+        machine,
+        RunLimit::Frames(frames),
+        &KeyScript::new(),
+        &mut NullSink,
+    )
+    .expect("Instruction faulted.");
+}
+
+fn snapshot(machine: &Machine) -> String {
+    headless::display_to_ascii(machine.ppu()) + &headless::registers_to_string(machine)
+}
+
+#[test]
+fn restoring_a_state_replays_identically() {
+    let mut machine = machine_with_rom("MAZE");
+    run_frames(&mut machine, 20);
+    let state = machine.save_state();
+
+    run_frames(&mut machine, 40);
+    let expected = snapshot(&machine);
+
+    let mut restored = machine_with_rom("MAZE");
+    restored.seed_rng(99);
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.cycles(), 200);
+    run_frames(&mut restored, 40);
+
+    assert_eq!(snapshot(&restored), expected);
+    assert_eq!(restored.save_state(), machine.save_state());
+}
+
+#[test]
+fn states_from_another_rom_are_rejected() {
+    let state = machine_with_rom("MAZE").save_state();
+    let mut machine = machine_with_rom("PONG");
+    let before = machine.save_state();
+
+    assert_eq!(machine.load_state(&state), Err(StateError::RomMismatch));
+    assert_eq!(machine.save_state(), before);
+}
+
+#[test]
+fn damaged_states_are_rejected() {
+    let mut machine = machine_with_rom("MAZE");
+    let state = machine.save_state();
+
+    assert_eq!(machine.load_state(b"hello"), Err(StateError::NotAState));
+    assert_eq!(
+        machine.load_state(&state[..state.len() - 1]),
+        Err(StateError::Corrupt)
+    );
+
+    let mut future = state.clone();
+    future[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+    assert_eq!(
+        machine.load_state(&future),
+        Err(StateError::UnsupportedVersion(VERSION + 1))
+    );
+}
+
+#[test]
+fn out_of_range_values_are_rejected() {
+    let mut machine = machine_with_rom("MAZE");
+    run_frames(&mut machine, 20);
+
+    // The display follows the 4 KB of memory, which holds the ROM at 0x200
+    let state = machine.save_state();
+    let rom = Rom::build("roms/MAZE").unwrap().data;
+    let memory = state
+        .windows(rom.len())
+        .position(|bytes| bytes == rom)
+        .unwrap()
+        - 0x200;
+    let display = memory + 0x1000;
+
+    let mut pixel = state.clone();
+    pixel[display] = 4;
+    assert_eq!(machine.load_state(&pixel), Err(StateError::Corrupt));
+
+    let mut planes = state.clone();
+    planes[display + 128 * 64 + 2] = 4;
+    assert_eq!(machine.load_state(&planes), Err(StateError::Corrupt));
+
+    // A sequence position past the end, just before the cycle and frame
+    // counts
+    machine.set_rng(Rng::sequence(vec![1, 2, 3]));
+    let mut state = machine.save_state();
+    let position = state.len() - 16 - 4;
+    assert_eq!(state[position], 0);
+    state[position] = 3;
+    assert_eq!(machine.load_state(&state), Err(StateError::Corrupt));
+    state[position] = 2;
+    assert_eq!(machine.load_state(&state), Ok(()));
+}
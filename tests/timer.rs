@@ -0,0 +1,31 @@
+use chip8::Quirks;
+
+mod common;
+
+use common::machine_with;
+
+/// Sets the delay timer to 60, then keeps reading it into V1.
+const PROGRAM: [u8; 8] = [
+    0x60, 0x3C, // V0 = 60
+    0xF0, 0x15, // delay timer = V0
+    0xF1, 0x07, // V1 = delay timer
+    0x12, 0x04, // jump to 0x204
+];
+
+fn delay_after(frames: usize, instructions_per_frame: usize) -> u8 {
+    let mut machine = machine_with(Quirks::default(), &PROGRAM);
+    machine.set_instructions_per_frame(instructions_per_frame);
+    for _ in 0..frames {
+        machine.run_frame().expect("Instruction faulted.");
+    }
+    machine.cpu().registers.get_vn(1)
+}
+
+#[test]
+fn delay_timer_drops_once_per_frame() {
+    // The last read happens before the tenth frame's tick
+    assert_eq!(delay_after(10, 5), 51);
+    assert_eq!(delay_after(10, 50), 51);
+    assert_eq!(delay_after(1, 500), 60);
+    assert_eq!(delay_after(100, 7), 0);
+}
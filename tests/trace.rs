@@ -0,0 +1,69 @@
+use std::env;
+use std::fs;
+
+use chip8::trace::{TraceFilter, Tracer};
+use chip8::{Machine, Quirks, Rom};
+
+fn trace(name: &str, filter: TraceFilter, program: &[u8], instructions: usize) -> String {
+    let path = env::temp_dir().join(format!("chip8-trace-{}-{}.log", name, std::process::id()));
+    let path = path.to_str().expect("Temporary path is not UTF-8.");
+
+    let mut machine = Machine::new(Quirks::default());
+    machine
+        .load_rom(Rom {
+            data: program.to_vec(),
+        })
+        .expect("Error loading ROM.");
+    machine.set_tracer(Some(
+        Tracer::create(path, filter).expect("Error creating trace."),
+    ));
+
+    for _ in 0..instructions {
+        machine.run_instruction().expect("Instruction faulted.");
+    }
+    machine
+        .take_tracer()
+        .expect("Tracer was detached.")
+        .finish()
+        .expect("Error writing trace.");
+
+    let log = fs::read_to_string(path).expect("Error reading trace.");
+    let _ = fs::remove_file(path);
+    log
+}
+
+const PROGRAM: [u8; 10] = [
+    0x60, 0x05, // V0 = 5
+    0xA3, 0x00, // I = 0x300
+    0x22, 0x08, // call 0x208
+    0x00, 0x00, // not reached
+    0x80, 0x0E, // V0 <<= 1
+];
+
+#[test]
+fn records_register_changes() {
+    let log = trace("all", TraceFilter::new(), &PROGRAM, 4);
+
+    let expected = "\
+00000001 0200 6005 LD V0, 0x05          V0=05
+00000002 0202 A300 LD I, 0x300          I=0300
+00000003 0204 2208 CALL 0x208           SP=1
+00000004 0208 800E SHL V0, V0           V0=0A
+";
+    assert_eq!(log, expected);
+}
+
+#[test]
+fn filters_by_address_and_family() {
+    let filter = TraceFilter {
+        addresses: Some(TraceFilter::parse_addresses("202-208").unwrap()),
+        families: Some(TraceFilter::parse_families("6,8,A").unwrap()),
+    };
+    let log = trace("filtered", filter, &PROGRAM, 4);
+
+    let addresses: Vec<&str> = log
+        .lines()
+        .map(|line| line.split_whitespace().nth(1).unwrap())
+        .collect();
+    assert_eq!(addresses, ["0202", "0208"]);
+}
@@ -0,0 +1,60 @@
+use chip8::config::Config;
+use chip8::viewport::{Scaling, Viewport};
+
+const LORES: (u32, u32) = (64, 32);
+const HIRES: (u32, u32) = (128, 64);
+
+fn viewport(x: i32, y: i32, width: u32, height: u32) -> Viewport {
+    Viewport {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+#[test]
+fn display_keeps_its_shape_by_default() {
+    let window = (1000, 400);
+
+    assert_eq!(
+        Viewport::fit(LORES, window, Scaling::default()),
+        viewport(100, 0, 800, 400)
+    );
+    assert_eq!(
+        Viewport::fit(HIRES, window, Scaling::Aspect),
+        viewport(100, 0, 800, 400)
+    );
+    assert_eq!(
+        Viewport::fit(LORES, window, Scaling::Stretch),
+        viewport(0, 0, 1000, 400)
+    );
+}
+
+#[test]
+fn integer_scaling_uses_whole_multiples() {
+    assert_eq!(
+        Viewport::fit(LORES, (1000, 700), Scaling::Integer),
+        viewport(20, 110, 960, 480)
+    );
+    assert_eq!(
+        Viewport::fit(HIRES, (1000, 700), Scaling::Integer),
+        viewport(52, 126, 896, 448)
+    );
+
+    // Too small for even one pixel per pixel
+    assert_eq!(
+        Viewport::fit(HIRES, (100, 100), Scaling::Integer),
+        viewport(0, 25, 100, 50)
+    );
+}
+
+#[test]
+fn scaling_is_read_from_the_config() {
+    let config = Config::parse("[display]\nscaling = \"Integer\"\nfullscreen = true").unwrap();
+    assert_eq!(config.scaling, Scaling::Integer);
+    assert!(config.fullscreen);
+
+    assert_eq!(Config::parse("").unwrap().scaling, Scaling::Aspect);
+    assert!(Config::parse("[display]\nscaling = \"zoom\"").is_err());
+}
@@ -0,0 +1,131 @@
+use chip8::audio::{AudioPattern, Sound};
+use chip8::{ExecError, Quirks};
+
+mod common;
+
+use common::{machine_with, run};
+
+#[test]
+fn long_index_load_is_skipped_as_one_instruction() {
+    let program = [
+        0xF0, 0x00, 0x12, 0x34, // I = 0x1234
+        0x30, 0x00, // skip if V0 == 0
+        0xF0, 0x00, 0x56, 0x78, // I = 0x5678
+        0x61, 0x01, // V1 = 1
+    ];
+
+    let mut machine = machine_with(Quirks::XO_CHIP, &program);
+    run(&mut machine, 3);
+
+    let registers = &machine.cpu().registers;
+    assert_eq!(registers.get_i(), 0x1234);
+    assert_eq!(registers.get_vn(1), 1);
+}
+
+#[test]
+fn register_ranges_are_saved_and_loaded() {
+    let program = [
+        0x61, 0x11, // V1 = 0x11
+        0x62, 0x22, // V2 = 0x22
+        0x63, 0x33, // V3 = 0x33
+        0xA3, 0x00, // I = 0x300
+        0x51, 0x32, // save V1-V3
+        0x56, 0x43, // load V6 down to V4
+    ];
+
+    let mut machine = machine_with(Quirks::XO_CHIP, &program);
+    run(&mut machine, 6);
+
+    let registers = &machine.cpu().registers;
+    assert_eq!(registers.get_i(), 0x300);
+    assert_eq!(machine.ram().read_byte(0x302), Ok(0x33));
+    assert_eq!(registers.get_vn(6), 0x11);
+    assert_eq!(registers.get_vn(5), 0x22);
+    assert_eq!(registers.get_vn(4), 0x33);
+}
+
+#[test]
+fn sprites_are_drawn_to_the_selected_planes() {
+    let program = [
+        0xF2, 0x01, // select plane 2
+        0xA0, 0x00, // I = font sprite for 0
+        0x60, 0x00, // V0 = 0
+        0xD0, 0x01, // draw one row at (0, 0)
+        0xF3, 0x01, // select both planes
+        0xA2, 0x10, // I = 0x210
+        0xD0, 0x01, // draw one row per plane at (0, 0)
+        0x12, 0x0E, // loop
+        0x80, 0xC0, // plane 1 row
+    ];
+
+    let mut machine = machine_with(Quirks::XO_CHIP, &program);
+    run(&mut machine, 4);
+    assert_eq!(machine.display()[0][..5], [2, 2, 2, 2, 0]);
+
+    run(&mut machine, 3);
+    assert_eq!(machine.display()[0][..5], [1, 0, 2, 2, 0]);
+}
+
+#[test]
+fn extended_memory_is_addressable() {
+    let program = [
+        0xF0, 0x00, 0x80, 0x00, // I = 0x8000
+        0x60, 0x42, // V0 = 0x42
+        0xF0, 0x55, // store V0
+    ];
+
+    let mut machine = machine_with(Quirks::XO_CHIP, &program);
+    run(&mut machine, 3);
+    assert_eq!(machine.ram().read_byte(0x8000), Ok(0x42));
+
+    let mut machine = machine_with(Quirks::COSMAC_VIP, &program);
+    run(&mut machine, 2);
+    assert_eq!(
+        machine.run_instruction(),
+        Err(ExecError::MemoryOutOfBounds { address: 0x8000 })
+    );
+}
+
+#[test]
+fn audio_pattern_plays_while_sound_timer_runs() {
+    let program = [
+        0xA2, 0x0C, // I = 0x20C
+        0xF0, 0x02, // load pattern
+        0x60, 0x70, // V0 = 0x70
+        0xF0, 0x3A, // pitch = V0
+        0xF0, 0x18, // sound timer = V0
+        0x12, 0x0A, // loop
+        0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0,
+        0xF0,
+    ];
+
+    let mut machine = machine_with(Quirks::XO_CHIP, &program);
+    run(&mut machine, 4);
+    assert_eq!(machine.sound(), Sound::Silent);
+
+    run(&mut machine, 1);
+    assert_eq!(
+        machine.sound(),
+        Sound::Pattern(AudioPattern {
+            buffer: [0xF0; 16],
+            pitch: 0x70,
+        })
+    );
+}
+
+#[test]
+fn call_in_the_last_word_of_memory_faults() {
+    // Fill memory with `V0 = 0` up to a call at 0xFFFE
+    let mut program = [0x60, 0x00].repeat((0xFFFE - 0x200) / 2);
+    program.extend([0x23, 0x00]);
+
+    let mut machine = machine_with(Quirks::XO_CHIP, &program);
+    let error = loop {
+        if let Err(err) = machine.run_instruction() {
+            break err;
+        }
+    };
+
+    assert_eq!(error, ExecError::PcOutOfBounds { address: 0xFFFE });
+    assert_eq!(machine.cpu().program_counter.get_value(), 0xFFFE);
+}